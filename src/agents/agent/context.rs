@@ -0,0 +1,229 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rig::{completion::ToolDefinition, message::Message};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agents::agent::VizierAgent,
+    schema::{
+        SessionHistory, SessionHistoryContent, VizierChannelId, VizierResponseContent,
+        VizierResponseStats, VizierSession,
+    },
+    storage::state::StateStorage,
+};
+
+// rough per message overhead of role markers and separators
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// summary of the turns that no longer fit in the context window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub content: String,
+    /// timestamp of the latest turn covered by the summary
+    pub until: DateTime<Utc>,
+}
+
+/// cheap token estimation (~4 characters per token), close enough for budgeting
+/// without pulling in a tokenizer for every provider
+pub fn estimate_tokens(message: &Message) -> usize {
+    serde_json::to_string(message)
+        .map(|raw| raw.len() / 4)
+        .unwrap_or(0)
+        + MESSAGE_OVERHEAD
+}
+
+fn estimate_tools_tokens(tools: &[ToolDefinition]) -> usize {
+    serde_json::to_string(tools)
+        .map(|raw| raw.len() / 4)
+        .unwrap_or(0)
+}
+
+fn history_to_message(history: &SessionHistory) -> Message {
+    match &history.content {
+        SessionHistoryContent::Request(req) => Message::user(req.to_prompt().unwrap()),
+        SessionHistoryContent::Response(r) => {
+            if let VizierResponseContent::Message { content, .. } = &r.content {
+                Message::assistant(content.clone())
+            } else {
+                Message::assistant("".to_string())
+            }
+        }
+    }
+}
+
+fn summary_key(session: &VizierSession) -> String {
+    format!("{}__summary", session.to_slug())
+}
+
+fn summary_message(summary: &SessionSummary) -> Message {
+    Message::system(format!(
+        "# Summary of Earlier Conversation\n{}",
+        summary.content
+    ))
+}
+
+impl VizierAgent {
+    /// builds the prompt history out of the system prompts and the session
+    /// turns, keeping it within the agent's context budget by summarizing
+    /// (or dropping) the oldest turns
+    pub(super) async fn assemble_context(
+        &self,
+        session: &VizierSession,
+        system: Vec<Message>,
+        session_history: Vec<SessionHistory>,
+        request: &Message,
+        tools: &[ToolDefinition],
+    ) -> Result<(Vec<Message>, Option<VizierResponseStats>)> {
+        // dream sessions read someone else's history, nothing to persist there
+        let persist = !matches!(session.1, VizierChannelId::Dream(_));
+
+        let mut summary = if persist {
+            self.storage
+                .get_state(summary_key(session))
+                .await?
+                .and_then(|value| serde_json::from_value::<SessionSummary>(value).ok())
+        } else {
+            None
+        };
+
+        let session_history = session_history
+            .into_iter()
+            .filter(|history| match &summary {
+                Some(summary) => history.timestamp() > summary.until,
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        let mut res = system;
+
+        let Some(budget) = self.config.context_budget() else {
+            res.extend(summary.as_ref().map(summary_message));
+            res.extend(session_history.iter().map(history_to_message));
+            return Ok((res, None));
+        };
+
+        let compaction = self.config.context.compaction;
+
+        let fixed = res.iter().map(estimate_tokens).sum::<usize>()
+            + estimate_tokens(request)
+            + estimate_tools_tokens(tools);
+        if fixed > budget {
+            log::warn!(
+                "{}: system prompts and tools alone take ~{} tokens, over the budget of {}",
+                session.to_slug(),
+                fixed,
+                budget
+            );
+        }
+
+        // leave room for the summary, it has to fit alongside the kept turns
+        let summary_budget = if compaction { budget / 8 } else { 0 };
+        let available = budget.saturating_sub(fixed + summary_budget);
+
+        let messages = session_history
+            .iter()
+            .map(history_to_message)
+            .collect::<Vec<_>>();
+        let total = messages.iter().map(estimate_tokens).sum::<usize>();
+
+        let mut split = 0;
+        let mut summary_stats = None;
+        if total > available {
+            // compact down to half of the available space, so the next few turns
+            // don't immediately trigger another round of summarization
+            let target = available / 2;
+
+            let mut used = 0;
+            split = messages.len();
+            for message in messages.iter().rev() {
+                let tokens = estimate_tokens(message);
+                if used + tokens > target {
+                    break;
+                }
+                used += tokens;
+                split -= 1;
+            }
+        }
+
+        if split > 0 {
+            let (dropped, _) = session_history.split_at(split);
+            if compaction {
+                match self
+                    .summarize(summary.clone(), &messages[..split], summary_budget)
+                    .await
+                {
+                    Ok((content, stats)) => {
+                        summary_stats = Some(stats);
+                        let new_summary = SessionSummary {
+                            content,
+                            until: dropped.last().unwrap().timestamp(),
+                        };
+
+                        if persist {
+                            self.storage
+                                .save_state(
+                                    summary_key(session),
+                                    serde_json::to_value(new_summary.clone())?,
+                                )
+                                .await?;
+                        }
+
+                        summary = Some(new_summary);
+                    }
+                    Err(err) => {
+                        log::error!("{}: history compaction: {}", session.to_slug(), err)
+                    }
+                }
+            } else {
+                log::debug!(
+                    "{}: dropping {} turns to fit the context budget",
+                    session.to_slug(),
+                    split
+                );
+            }
+        }
+
+        res.extend(summary.as_ref().map(summary_message));
+        res.extend(messages.into_iter().skip(split));
+
+        Ok((res, summary_stats))
+    }
+
+    async fn summarize(
+        &self,
+        previous: Option<SessionSummary>,
+        turns: &[Message],
+        max_tokens: usize,
+    ) -> Result<(String, VizierResponseStats)> {
+        let transcript = turns
+            .iter()
+            .map(|message| serde_json::to_string(message).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let prompt = format!(
+            r#"summarize (don't continue) the conversation below for your own future reference.
+keep facts, decisions, user preferences and open tasks, drop small talk.
+{}
+# Conversation
+{}
+
+**only response the summary, in less than {} words**"#,
+            previous
+                .map(|summary| format!("# Previous Summary\n{}\n", summary.content))
+                .unwrap_or_default(),
+            transcript,
+            max_tokens * 3 / 4,
+        );
+
+        let (mut content, stats) = self.complete(prompt).await?;
+
+        // the model doesn't always respect the limit
+        let max_chars = max_tokens * 4;
+        if content.chars().count() > max_chars {
+            content = content.chars().take(max_chars).collect();
+        }
+
+        Ok((content, stats))
+    }
+}
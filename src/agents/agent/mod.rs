@@ -1,38 +1,68 @@
-use std::{fs, sync::Arc};
+use std::{collections::HashMap, fs, sync::Arc};
 
 use anyhow::Result;
 use chrono::Utc;
 use rand::{RngExt, SeedableRng, rngs::StdRng};
 use rig::{
     OneOrMany,
-    message::{AssistantContent, Message},
+    message::{AssistantContent, Message, ToolCall, UserContent},
 };
 use serde::{Deserialize, Serialize};
-use tokio::time::{Instant, timeout};
+use tokio::{
+    sync::{RwLock, Semaphore},
+    time::{Duration, Instant, timeout},
+};
 
 use crate::{
     VizierError,
     agents::{
         agent::{
-            model::{VizierModel, VizierModelTrait},
+            model::{VizierCompletion, VizierModel, VizierModelTrait},
             system_prompt::{boot::boot_md, init_workspace, user::primary_user_md},
         },
         hook::{VizierSessionHook, VizierSessionHooks},
         skill::VizierSkills,
-        tools::VizierTools,
+        tools::{VizierTools, current_tool_session, with_tool_session},
+    },
+    config::{
+        agent::{AgentConfig, ModelFallbackConfig},
+        provider::ModelPricing,
+        user::UserConfig,
     },
-    config::{agent::AgentConfig, user::UserConfig},
     dependencies::VizierDependencies,
+    error::VizierToolDenied,
     schema::{
-        Memory, SessionHistory, SessionHistoryContent, VizierRequest, VizierRequestContent,
-        VizierResponse, VizierResponseContent, VizierResponseStats,
+        Memory, SessionHistory, VizierAttachment, VizierRequest, VizierRequestContent,
+        VizierResponse, VizierResponseContent, VizierResponseStats, VizierSession,
     },
+    storage::VizierStorage,
     utils::{agent_workspace, build_path},
 };
 
+mod context;
 mod model;
+mod permission;
+mod reasoning;
+mod structured;
 mod system_prompt;
 
+pub use permission::{SenderTier, sender_id};
+pub use structured::SCHEMA_MISMATCH;
+
+const DEFAULT_MAX_CONCURRENT_TOOL_CALLS: usize = 4;
+
+tokio::task_local! {
+    /// when the running turn hits the prompt timeout
+    static TURN_DEADLINE: Instant;
+}
+
+/// time left before the running turn times out, none outside of a turn
+pub fn turn_time_left() -> Option<Duration> {
+    TURN_DEADLINE
+        .try_with(|deadline| deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
 pub struct Subtask {
     title: String,
@@ -54,6 +84,8 @@ pub struct VizierAgent {
     skills: VizierSkills,
     config: AgentConfig,
     primary_user: UserConfig,
+    storage: Arc<VizierStorage>,
+    pricing: HashMap<String, ModelPricing>,
 }
 
 impl VizierAgent {
@@ -75,10 +107,25 @@ impl VizierAgent {
             skills,
             config: agent_config.clone(),
             primary_user: deps.config.primary_user.clone(),
+            storage: deps.storage.clone(),
+            pricing: deps.config.providers.pricing.clone(),
             workspace,
         })
     }
 
+    /// same agent answering with another model, e.g. once its budget is spent
+    pub async fn with_model(
+        &self,
+        agent_id: String,
+        model: ModelFallbackConfig,
+        deps: &VizierDependencies,
+    ) -> Result<VizierAgent> {
+        let mut agent = self.clone();
+        agent.model = VizierModel::single(agent_id, model, deps.clone()).await?;
+
+        Ok(agent)
+    }
+
     pub async fn prepare_system_prompts(&self) -> Vec<Message> {
         // init workspace just in case
         init_workspace(self.workspace.clone());
@@ -109,6 +156,7 @@ impl VizierAgent {
 
     pub async fn chat(
         &self,
+        session: VizierSession,
         req: VizierRequest,
         session_history: Vec<SessionHistory>,
         memory: Vec<Memory>,
@@ -143,21 +191,6 @@ impl VizierAgent {
             )));
         }
 
-        history.extend(
-            session_history
-                .iter()
-                .map(|history| match &history.content {
-                    SessionHistoryContent::Request(req) => Message::user(req.to_prompt().unwrap()),
-                    SessionHistoryContent::Response(r) => {
-                        if let VizierResponseContent::Message { content, .. } = &r.content {
-                            Message::assistant(content.clone())
-                        } else {
-                            Message::assistant("".to_string())
-                        }
-                    }
-                }),
-        );
-
         let mut req = req;
         if let Some(hooks) = hooks.clone() {
             req = hooks.on_request(req).await?;
@@ -173,17 +206,43 @@ impl VizierAgent {
             }
         }
 
-        let (output, stats) = self
-            .prompt(req.to_message()?, history, 0, hooks.clone(), false)
+        if let Some(schema) = &req.response_schema {
+            history.push(structured::schema_instruction(schema));
+        }
+
+        let tier = self.sender_tier(&session.1, &req);
+        let message = req.to_message()?;
+        let (history, summary_stats) = self
+            .assemble_context(&session, history, session_history, &message, &tools)
             .await?;
 
+        // tools like the python sandbox keep their state per session
+        let (output, mut stats, attachments) = with_tool_session(Some(session), async {
+            anyhow::Ok(match &req.response_schema {
+                Some(schema) => {
+                    let (value, stats, attachments) = self
+                        .structured_prompt(schema, message, history, hooks.clone(), tier)
+                        .await?;
+                    (serde_json::to_string(&value)?, stats, attachments)
+                }
+                None => {
+                    self.prompt(message, history, 0, hooks.clone(), false, tier)
+                        .await?
+                }
+            })
+        })
+        .await?;
+        if let Some(summary_stats) = summary_stats {
+            stats.add_usage(&summary_stats);
+        }
+
         let mut response = VizierResponse {
             timestamp: chrono::Utc::now(),
             content: VizierResponseContent::Message {
                 content: output,
                 stats: Some(stats),
             },
-            attachments: vec![],
+            attachments,
         };
         if let Some(hooks) = hooks.clone() {
             response = hooks.on_response(response).await?;
@@ -192,6 +251,52 @@ impl VizierAgent {
         Ok(response)
     }
 
+    /// a single completion without tools, for the agent's own bookkeeping like
+    /// session titles and summaries. whatever the prompt quotes can't make the
+    /// model act on anything
+    pub async fn complete(&self, prompt: String) -> Result<(String, VizierResponseStats)> {
+        let start = Instant::now();
+        let VizierCompletion {
+            choice,
+            usage,
+            model,
+            ..
+        } = timeout(
+            *self.config.prompt_timeout,
+            self.model
+                .completion(Message::system(prompt), vec![], vec![]),
+        )
+        .await??;
+
+        let cost = self.pricing.get(&model).map(|pricing| {
+            pricing.cost(
+                usage.input_tokens,
+                usage.cached_input_tokens,
+                usage.output_tokens,
+            )
+        });
+        let text = choice
+            .iter()
+            .filter(|choice| !matches!(choice, AssistantContent::ToolCall(_)))
+            .collect::<Vec<_>>();
+
+        Ok((
+            reasoning::text_of(&text),
+            VizierResponseStats {
+                total_tokens: usage.total_tokens,
+                total_cached_input_tokens: usage.cached_input_tokens,
+                total_input_tokens: usage.input_tokens,
+                total_output_tokens: usage.output_tokens,
+                input_tokens: usage.input_tokens,
+                cached_input_tokens: usage.cached_input_tokens,
+                duration: start.elapsed(),
+                model: Some(model),
+                cost,
+                reasoning: None,
+            },
+        ))
+    }
+
     pub async fn prompt(
         &self,
         message: Message,
@@ -199,13 +304,21 @@ impl VizierAgent {
         turn_depth: usize,
         hooks: Option<Arc<VizierSessionHooks>>,
         is_subagent: bool,
-    ) -> Result<(String, VizierResponseStats)> {
-        timeout(*self.config.prompt_timeout, async {
+        tier: SenderTier,
+    ) -> Result<(String, VizierResponseStats, Vec<VizierAttachment>)> {
+        // a nested prompt can't outlast the one around it
+        let deadline = Instant::now() + *self.config.prompt_timeout;
+        let deadline = TURN_DEADLINE
+            .try_with(|outer| (*outer).min(deadline))
+            .unwrap_or(deadline);
+
+        let turn = TURN_DEADLINE.scope(deadline, async {
             let mut history = history.clone();
             let mut turn_depth = turn_depth;
             let max_turn_depth = self.config.thinking_depth;
-            let mut tools = self.tools.tools().await?;
-            tools.extend(self.skills.get_skills().await?);
+            let mut all_tools = self.tools.tools().await?;
+            all_tools.extend(self.skills.get_skills().await?);
+            let mut tier = tier;
 
             let output: String;
 
@@ -219,6 +332,10 @@ impl VizierAgent {
             let mut total_input_tokens: u64 = 0;
             let mut total_output_tokens: u64 = 0;
             let mut total_tokens: u64 = 0;
+            let mut model: Option<String>;
+            let mut cost: Option<f64> = None;
+            let mut turn_reasoning: Vec<String> = vec![];
+            let mut attachments: Vec<VizierAttachment> = vec![];
 
             loop {
                 turn_depth += 1;
@@ -229,101 +346,157 @@ impl VizierAgent {
                     ))));
                 }
 
-                let (message_id, choices, usage) = self
-                    .model
-                    .completion(message.clone(), history.clone(), tools.clone())
-                    .await?;
+                // the model only sees the tools the sender may use
+                let tools = all_tools
+                    .iter()
+                    .filter(|tool| {
+                        self.tools.is_enabled(&tool.name) && self.is_permitted(&tool.name, tier)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                let completion = match (hooks.clone(), self.config.stream_response) {
+                    (Some(hooks), Some(true)) => {
+                        let (delta_tx, delta_rx) = flume::unbounded::<String>();
+                        let forward = tokio::spawn(async move {
+                            while let Ok(delta) = delta_rx.recv_async().await {
+                                if let Err(err) = hooks.on_message_delta(delta).await {
+                                    log::error!("message delta: {}", err);
+                                }
+                            }
+                        });
+
+                        let res = self
+                            .model
+                            .stream_completion(
+                                message.clone(),
+                                history.clone(),
+                                tools.clone(),
+                                delta_tx,
+                            )
+                            .await;
+
+                        // make sure every delta is delivered before the final message
+                        let _ = forward.await;
+                        res?
+                    }
+                    _ => {
+                        self.model
+                            .completion(message.clone(), history.clone(), tools.clone())
+                            .await?
+                    }
+                };
+
+                let VizierCompletion {
+                    message_id,
+                    choice: choices,
+                    usage,
+                    model: answered_by,
+                } = completion;
 
                 history.push(message);
 
+                // reasoning blocks stay in the history, providers like anthropic
+                // expect them back alongside the tool results
                 history.push(Message::Assistant {
                     id: message_id.clone(),
                     content: choices.clone(),
                 });
 
+                if let Some(pricing) = self.pricing.get(&answered_by) {
+                    *cost.get_or_insert(0.0) += pricing.cost(
+                        usage.input_tokens,
+                        usage.cached_input_tokens,
+                        usage.output_tokens,
+                    );
+                }
+                model = Some(answered_by);
+
                 if turn_depth == 1 {
                     input_tokens = usage.input_tokens;
-                    cached_input_tokens = usage.input_tokens;
+                    cached_input_tokens = usage.cached_input_tokens;
                 }
 
                 total_input_tokens += usage.input_tokens;
-                total_cached_input_tokens += usage.input_tokens;
+                total_cached_input_tokens += usage.cached_input_tokens;
                 total_output_tokens += usage.output_tokens;
                 total_tokens += usage.total_tokens;
 
+                if let Some(reasoning) = reasoning::reasoning_of(&choices) {
+                    if let Some(hooks) = hooks.clone() {
+                        hooks.on_reasoning(reasoning.clone()).await?;
+                    }
+                    turn_reasoning.push(reasoning);
+                }
+
                 let (tool_calls, others): (Vec<_>, Vec<_>) = choices
                     .iter()
                     .partition(|choice| matches!(choice, AssistantContent::ToolCall(_)));
 
                 if tool_calls.is_empty() {
-                    output = others
-                        .iter()
-                        .filter_map(|item| {
-                            if let AssistantContent::Text(text) = item {
-                                Some(text.to_string())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n");
+                    output = reasoning::text_of(&others);
 
                     break;
                 }
 
-                let mut tool_responses = vec![];
-                for call in tool_calls.iter().filter_map(|item| {
-                    if let AssistantContent::ToolCall(call) = item {
-                        Some(call)
-                    } else {
-                        None
-                    }
-                }) {
-                    let (mut function_name, mut args) = (
-                        call.function.name.clone(),
-                        serde_json::to_string(&call.function.arguments).unwrap(),
-                    );
-                    if let Some(hooks) = hooks.clone() {
-                        (function_name, args) = hooks.on_tool_call(function_name, args).await?;
-                    }
-
-                    // handle custom skill
-                    let mut tool_res = if function_name.clone().starts_with("SKILL__") {
-                        let output = self.call_skill(function_name.clone()).await;
-                        VizierResponse {
-                            timestamp: Utc::now(),
-                            content: VizierResponseContent::ToolResponse {
-                                response: serde_json::Value::String(output),
-                            },
-                            attachments: vec![],
-                        }
-                    } else {
-                        let tool_server = self.tools.clone();
-                        match timeout(
-                            *self.config.tools.timeout,
-                            tokio::spawn(async move {
-                                tool_server.call(function_name.clone(), args).await
+                // every call waits for a concurrency slot, exclusive ones also
+                // wait for the whole turn to be quiet before running alone
+                let semaphore = Semaphore::new(
+                    self.config
+                        .tools
+                        .max_concurrent_calls
+                        .unwrap_or(DEFAULT_MAX_CONCURRENT_TOOL_CALLS)
+                        .max(1),
+                );
+                let exclusive = RwLock::new(());
+
+                // try_join_all keeps the results in the same order as the calls
+                let (mut tool_responses, tool_attachments): (Vec<_>, Vec<_>) =
+                    futures::future::try_join_all(
+                        tool_calls
+                            .iter()
+                            .filter_map(|item| {
+                                if let AssistantContent::ToolCall(call) = item {
+                                    Some(call)
+                                } else {
+                                    None
+                                }
+                            })
+                            .map(|call| {
+                                let semaphore = &semaphore;
+                                let exclusive = &exclusive;
+                                let hooks = hooks.clone();
+                                async move {
+                                    let _permit = semaphore.acquire().await?;
+                                    if self.tools.is_exclusive(&call.function.name) {
+                                        let _guard = exclusive.write().await;
+                                        self.call_tool(call, hooks, tier).await
+                                    } else {
+                                        let _guard = exclusive.read().await;
+                                        self.call_tool(call, hooks, tier).await
+                                    }
+                                }
                             }),
-                        )
-                        .await??
-                        {
-                            Err(err) => VizierResponse {
-                                timestamp: Utc::now(),
-                                content: VizierResponseContent::ToolResponse {
-                                    response: serde_json::Value::String(err.to_string()),
-                                },
-                                attachments: vec![],
-                            },
-                            Ok(s) => s,
+                    )
+                    .await?
+                    .into_iter()
+                    .unzip();
+                attachments.extend(tool_attachments.into_iter().flatten());
+
+                if let Some(hooks) = hooks.clone() {
+                    // requests merged into the running turn
+                    for req in hooks.on_turn_boundary().await? {
+                        let req = hooks.on_request(req).await?;
+                        // the rest of the turn is limited to what every sender may use
+                        // merged requests come from the session of the turn
+                        tier = tier.min(match current_tool_session() {
+                            Some(session) => self.sender_tier(&session.1, &req),
+                            None => SenderTier::Everyone,
+                        });
+                        if let Message::User { content } = req.to_message()? {
+                            tool_responses.extend(content);
                         }
-                    };
-
-                    if let Some(hooks) = hooks.clone() {
-                        tool_res = hooks.on_tool_response(tool_res).await?;
                     }
-                    tool_responses.push(
-                        tool_res.to_tool_response_content(call.id.clone(), call.call_id.clone())?,
-                    );
                 }
 
                 message = Message::User {
@@ -341,10 +514,110 @@ impl VizierAgent {
                     input_tokens,
                     cached_input_tokens,
                     duration: start.elapsed(),
+                    model,
+                    cost,
+                    reasoning: (!turn_reasoning.is_empty()).then(|| turn_reasoning.join("\n\n")),
                 },
+                attachments,
             ))
-        })
-        .await?
+        });
+
+        timeout(*self.config.prompt_timeout, turn).await?
+    }
+
+    async fn call_tool(
+        &self,
+        call: &ToolCall,
+        hooks: Option<Arc<VizierSessionHooks>>,
+        tier: SenderTier,
+    ) -> Result<(UserContent, Vec<VizierAttachment>)> {
+        let (mut function_name, mut args) = (
+            call.function.name.clone(),
+            serde_json::to_string(&call.function.arguments).unwrap(),
+        );
+        let mut denied = None;
+        if !self.tools.is_enabled(&function_name) {
+            denied = Some(format!(
+                "`{}` is not available to this agent",
+                function_name
+            ));
+        } else if !self.is_permitted(&function_name, tier) {
+            denied = Some(format!(
+                "`{}` is not available to the user of this request",
+                function_name
+            ));
+        } else if let Some(hooks) = hooks.clone() {
+            match hooks
+                .on_tool_call(function_name.clone(), args.clone())
+                .await
+            {
+                Ok(res) => (function_name, args) = res,
+                Err(err) => match err.downcast_ref::<VizierToolDenied>() {
+                    Some(VizierToolDenied(reason)) => denied = Some(reason.clone()),
+                    None => return Err(err),
+                },
+            }
+        }
+
+        // a denied call never reaches the tool, the model gets the reason instead
+        let mut tool_res = if let Some(reason) = denied {
+            VizierResponse {
+                timestamp: Utc::now(),
+                content: VizierResponseContent::ToolResponse {
+                    response: serde_json::Value::String(reason),
+                },
+                attachments: vec![],
+            }
+        } else if function_name.clone().starts_with("SKILL__") {
+            // handle custom skill
+            let output = self.call_skill(function_name.clone()).await;
+            VizierResponse {
+                timestamp: Utc::now(),
+                content: VizierResponseContent::ToolResponse {
+                    response: serde_json::Value::String(output),
+                },
+                attachments: vec![],
+            }
+        } else {
+            let tool_server = self.tools.clone();
+            let session = current_tool_session();
+            match timeout(
+                *self.config.tools.timeout,
+                tokio::spawn(with_tool_session(session, async move {
+                    tool_server.call(function_name.clone(), args).await
+                })),
+            )
+            .await??
+            {
+                Err(err) => VizierResponse {
+                    timestamp: Utc::now(),
+                    content: VizierResponseContent::ToolResponse {
+                        response: serde_json::Value::String(err.to_string()),
+                    },
+                    attachments: vec![],
+                },
+                Ok(s) => s,
+            }
+        };
+
+        if let Some(hooks) = hooks.clone() {
+            tool_res = hooks.on_tool_response(tool_res).await?;
+        }
+
+        // hooks see the whole result, the model only its preview when it's long
+        tool_res = self.tools.offload(&call.function.name, tool_res).await;
+
+        // files meant for the user skip the model, it only gets the tool's note
+        let attachments = if self.tools.delivers_attachments(&call.function.name) {
+            std::mem::take(&mut tool_res.attachments)
+        } else {
+            vec![]
+        };
+
+        Ok((
+            tool_res.to_tool_response_content(call.id.clone(), call.call_id.clone())?,
+            attachments,
+        ))
     }
 
     pub async fn call_skill(&self, skill_name: String) -> String {
@@ -0,0 +1,120 @@
+use anyhow::Result;
+use rig::{
+    client::CompletionClient,
+    completion::ToolDefinition,
+    message::{Message, ToolResultContent, UserContent},
+    providers::openai,
+};
+
+use crate::{
+    agents::agent::model::{VizierCompletion, VizierModelImpl, VizierModelTrait},
+    config::provider::CompatibleProviderConfig,
+    error::VizierError,
+    utils::openai::compatible_client,
+};
+
+const IMAGE_OMITTED: &str = "[image omitted, the model can't see images]";
+
+/// model behind a named OpenAI compatible server, requests are trimmed down to
+/// what the server supports
+pub struct CompatibleModel {
+    inner: VizierModelImpl<openai::CompletionsClient>,
+    tools: bool,
+    vision: bool,
+}
+
+impl CompatibleModel {
+    pub fn build(
+        provider: String,
+        model: String,
+        config: Option<&CompatibleProviderConfig>,
+    ) -> Result<Self> {
+        let config = config.ok_or(VizierError(format!(
+            "provider `{}` is not configured under providers.compatible",
+            provider
+        )))?;
+
+        // compatible servers implement chat completions, not the responses api
+        let client = compatible_client(config)?.completions_api();
+
+        Ok(Self {
+            inner: VizierModelImpl(
+                client.completion_model(&model),
+                format!("{}/{}", provider, model),
+            ),
+            tools: config.tools,
+            vision: config.vision,
+        })
+    }
+
+    fn adapt(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+    ) -> (Message, Vec<Message>, Vec<ToolDefinition>) {
+        let tools = if self.tools { tools } else { vec![] };
+        if self.vision {
+            return (message, history, tools);
+        }
+
+        (
+            strip_images(message),
+            history.into_iter().map(strip_images).collect(),
+            tools,
+        )
+    }
+}
+
+fn strip_images(message: Message) -> Message {
+    let mut message = message;
+    if let Message::User { content } = &mut message {
+        for item in content.iter_mut() {
+            match item {
+                UserContent::Image(_) => {
+                    *item = UserContent::Text(IMAGE_OMITTED.to_string().into());
+                }
+                UserContent::ToolResult(result) => {
+                    for item in result.content.iter_mut() {
+                        if let ToolResultContent::Image(_) = item {
+                            *item = ToolResultContent::Text(IMAGE_OMITTED.to_string().into());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    message
+}
+
+#[async_trait::async_trait]
+impl VizierModelTrait for CompatibleModel {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    async fn completion(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+    ) -> Result<VizierCompletion> {
+        let (message, history, tools) = self.adapt(message, history, tools);
+        self.inner.completion(message, history, tools).await
+    }
+
+    async fn stream_completion(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        delta: flume::Sender<String>,
+    ) -> Result<VizierCompletion> {
+        let (message, history, tools) = self.adapt(message, history, tools);
+        self.inner
+            .stream_completion(message, history, tools, delta)
+            .await
+    }
+}
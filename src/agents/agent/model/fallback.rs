@@ -0,0 +1,269 @@
+use std::{
+    future::Future,
+    sync::{
+        LazyLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use regex::Regex;
+use rig::{
+    completion::{CompletionError, ToolDefinition},
+    http_client,
+    message::Message,
+};
+
+use crate::{
+    agents::agent::model::{VizierCompletion, VizierModel, VizierModelTrait},
+    config::agent::RetryConfig,
+    error::VizierError,
+};
+
+static RETRY_AFTER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"retry[-_ ]after"?\s*[:=]?\s*"?(\d+(?:\.\d+)?)"#).unwrap());
+static TRY_AGAIN_IN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"try again in (\d+(?:\.\d+)?)\s*(ms|s)").unwrap());
+
+/// ordered chain of models, each one retried on transient errors before the
+/// next one in line takes over
+pub struct FallbackModel {
+    models: Vec<VizierModel>,
+    retry: RetryConfig,
+}
+
+impl FallbackModel {
+    pub fn new(models: Vec<VizierModel>, retry: RetryConfig) -> Self {
+        Self { models, retry }
+    }
+
+    fn backoff(&self, attempt: usize, retry_after: Option<Duration>) -> Duration {
+        let max_backoff = *self.retry.max_backoff;
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(max_backoff);
+        }
+
+        let backoff = self
+            .retry
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt as u32));
+
+        backoff.min(max_backoff)
+    }
+
+    /// calls each model in turn until one answers. once `emitted` is set the
+    /// caller already has part of an answer, so the error is returned as is
+    async fn with_retries<'a, F, Fut>(
+        &'a self,
+        emitted: Option<&AtomicBool>,
+        call: F,
+    ) -> Result<VizierCompletion>
+    where
+        F: Fn(&'a VizierModel) -> Fut,
+        Fut: Future<Output = Result<VizierCompletion>>,
+    {
+        let mut last_err = None;
+        for model in self.models.iter() {
+            let mut attempt = 0;
+            loop {
+                let err = match call(model).await {
+                    Ok(res) => return Ok(res),
+                    Err(err) => err,
+                };
+
+                if emitted.is_some_and(|emitted| emitted.load(Ordering::SeqCst)) {
+                    return Err(err);
+                }
+
+                let kind = classify_error(&err);
+                log::warn!("{} failed ({:?}): {}", model.name(), kind, err);
+
+                match kind {
+                    ErrorKind::Retryable(retry_after) if attempt < self.retry.max_retries => {
+                        tokio::time::sleep(self.backoff(attempt, retry_after)).await;
+                        attempt += 1;
+                    }
+                    _ => {
+                        last_err = Some(err);
+                        break;
+                    }
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or(VizierError("no model configured".into()).into()))
+    }
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    Retryable(Option<Duration>),
+    Fatal,
+}
+
+/// the http status a provider answered with, rig keeps it on its http errors
+fn status_of(err: &anyhow::Error) -> Option<u16> {
+    err.chain()
+        .find_map(|err| err.downcast_ref::<CompletionError>())
+        .and_then(|err| match err {
+            CompletionError::HttpError(
+                http_client::Error::InvalidStatusCode(status)
+                | http_client::Error::InvalidStatusCodeWithMessage(status, _),
+            ) => Some(status.as_u16()),
+            _ => None,
+        })
+}
+
+// errors without a status, e.g. a dropped connection, only have their message
+// to go by, and so does any retry hint in the body
+fn classify_error(err: &anyhow::Error) -> ErrorKind {
+    let message = err.to_string().to_lowercase();
+
+    let transient = match status_of(err) {
+        Some(status) => status == 408 || status == 429 || status >= 500,
+        None => [
+            "rate limit",
+            "overloaded",
+            "timed out",
+            "timeout",
+            "connection",
+        ]
+        .iter()
+        .any(|t| message.contains(t)),
+    };
+    if !transient {
+        return ErrorKind::Fatal;
+    }
+
+    let retry_after = RETRY_AFTER
+        .captures(&message)
+        .and_then(|caps| caps[1].parse::<f64>().ok())
+        .map(Duration::from_secs_f64)
+        .or_else(|| {
+            TRY_AGAIN_IN.captures(&message).and_then(|caps| {
+                let value = caps[1].parse::<f64>().ok()?;
+                Some(match &caps[2] {
+                    "ms" => Duration::from_secs_f64(value / 1000.),
+                    _ => Duration::from_secs_f64(value),
+                })
+            })
+        });
+
+    ErrorKind::Retryable(retry_after)
+}
+
+#[async_trait::async_trait]
+impl VizierModelTrait for FallbackModel {
+    fn name(&self) -> String {
+        self.models
+            .iter()
+            .map(|model| model.name())
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+
+    async fn completion(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+    ) -> Result<VizierCompletion> {
+        self.with_retries(None, |model| {
+            model.completion(message.clone(), history.clone(), tools.clone())
+        })
+        .await
+    }
+
+    async fn stream_completion(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        delta: flume::Sender<String>,
+    ) -> Result<VizierCompletion> {
+        let emitted = AtomicBool::new(false);
+        self.with_retries(Some(&emitted), |model| {
+            let (message, history, tools) = (message.clone(), history.clone(), tools.clone());
+            let (emitted, delta) = (&emitted, delta.clone());
+            async move {
+                // deltas go through here to know whether any got out
+                let (attempt_delta, attempt_deltas) = flume::unbounded::<String>();
+                let forward = |chunk: String| {
+                    emitted.store(true, Ordering::SeqCst);
+                    let _ = delta.send(chunk);
+                };
+
+                let completion = model.stream_completion(message, history, tools, attempt_delta);
+                tokio::pin!(completion);
+                loop {
+                    tokio::select! {
+                        res = &mut completion => {
+                            attempt_deltas.drain().for_each(forward);
+                            break res;
+                        }
+                        Ok(chunk) = attempt_deltas.recv_async() => forward(chunk),
+                    }
+                }
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rig::{completion::CompletionError, http_client};
+
+    use super::*;
+
+    fn status_error(status: u16, message: &str) -> anyhow::Error {
+        CompletionError::HttpError(http_client::Error::InvalidStatusCodeWithMessage(
+            status.try_into().unwrap(),
+            message.to_string(),
+        ))
+        .into()
+    }
+
+    #[test]
+    fn classify_rate_limits_as_retryable() {
+        let err = status_error(429, r#"{"error": "slow down", "retry_after": 3}"#);
+
+        assert!(matches!(
+            classify_error(&err),
+            ErrorKind::Retryable(Some(retry_after)) if retry_after == Duration::from_secs(3)
+        ));
+    }
+
+    #[test]
+    fn classify_server_errors_as_retryable() {
+        let err = status_error(503, "try again in 250ms");
+
+        assert!(matches!(
+            classify_error(&err),
+            ErrorKind::Retryable(Some(retry_after)) if retry_after == Duration::from_millis(250)
+        ));
+    }
+
+    #[test]
+    fn classify_client_errors_as_fatal() {
+        let err = status_error(401, "invalid api key");
+
+        assert!(matches!(classify_error(&err), ErrorKind::Fatal));
+    }
+
+    #[test]
+    fn classify_ignores_numbers_in_the_message() {
+        let err =
+            CompletionError::ProviderError("prompt is 5000 tokens, max is 4096".into()).into();
+
+        assert!(matches!(classify_error(&err), ErrorKind::Fatal));
+    }
+
+    #[test]
+    fn classify_connection_errors_as_retryable() {
+        let err = anyhow::anyhow!("error sending request: connection reset by peer");
+
+        assert!(matches!(classify_error(&err), ErrorKind::Retryable(None)));
+    }
+}
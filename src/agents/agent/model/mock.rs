@@ -0,0 +1,218 @@
+use std::{fs, sync::Mutex};
+
+use anyhow::Result;
+use reqwest::StatusCode;
+use rig::{
+    OneOrMany,
+    completion::{CompletionError, ToolDefinition, Usage},
+    http_client,
+    message::{AssistantContent, Message},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agents::agent::model::{VizierCompletion, VizierModelTrait},
+    error::VizierError,
+};
+
+/// scripted completions, loaded from a yaml (or json) file, e.g.
+///
+/// ```yaml
+/// completions:
+///   - matches: "60 character title"
+///     repeat: true
+///     text: "Test topic"
+///   - tool_calls:
+///       - name: think
+///         arguments: { thought: "checking the weather" }
+///     usage: { input_tokens: 120, output_tokens: 12 }
+///   - error: "Too Many Requests"
+///     status: 429
+///   - text: "it's sunny"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockScript {
+    pub completions: Vec<MockCompletion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockCompletion {
+    /// only answer messages containing this text, unset answers anything
+    #[serde(default)]
+    pub matches: Option<String>,
+    /// keep the entry around instead of consuming it
+    #[serde(default)]
+    pub repeat: bool,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<MockToolCall>,
+    #[serde(default)]
+    pub usage: Option<MockUsage>,
+    /// fail with this message instead, e.g. to exercise retries and fallbacks
+    #[serde(default)]
+    pub error: Option<String>,
+    /// http status of `error`, it fails like the provider answered with it
+    #[serde(default)]
+    pub status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cached_input_tokens: u64,
+}
+
+/// deterministic model for tests, `model` is the path of the script
+pub struct MockModel {
+    name: String,
+    completions: Mutex<Vec<MockCompletion>>,
+}
+
+impl MockModel {
+    pub fn load(path: String) -> Result<Self> {
+        let raw = fs::read_to_string(&path)
+            .map_err(|err| VizierError(format!("mock script {}: {}", path, err)))?;
+        let script: MockScript = serde_yaml::from_str(&raw)
+            .map_err(|err| VizierError(format!("mock script {}: {}", path, err)))?;
+
+        Ok(Self::new(format!("mock/{}", path), script))
+    }
+
+    pub fn new(name: String, script: MockScript) -> Self {
+        Self {
+            name,
+            completions: Mutex::new(script.completions),
+        }
+    }
+
+    fn next(&self, message: &Message) -> Result<MockCompletion> {
+        let texts = message_texts(message);
+
+        let mut completions = self.completions.lock().unwrap();
+        let index = completions
+            .iter()
+            .position(|completion| match &completion.matches {
+                Some(pattern) => texts.iter().any(|text| text.contains(pattern)),
+                None => true,
+            })
+            .ok_or(VizierError("mock script exhausted".into()))?;
+
+        let completion = if completions[index].repeat {
+            completions[index].clone()
+        } else {
+            completions.remove(index)
+        };
+
+        if let Some(err) = &completion.error {
+            return Err(match completion.status.map(StatusCode::from_u16) {
+                Some(Ok(status)) => CompletionError::HttpError(
+                    http_client::Error::InvalidStatusCodeWithMessage(status, err.clone()),
+                )
+                .into(),
+                _ => VizierError(err.clone()).into(),
+            });
+        }
+
+        Ok(completion)
+    }
+
+    fn to_completion(&self, completion: MockCompletion) -> Result<VizierCompletion> {
+        let mut choices = vec![];
+        if let Some(text) = completion.text {
+            choices.push(AssistantContent::text(text));
+        }
+
+        for (i, call) in completion.tool_calls.into_iter().enumerate() {
+            choices.push(AssistantContent::tool_call(
+                format!("mock_call_{}", i),
+                call.name,
+                call.arguments,
+            ));
+        }
+
+        let mut usage = Usage::new();
+        if let Some(mock_usage) = completion.usage {
+            usage.input_tokens = mock_usage.input_tokens;
+            usage.output_tokens = mock_usage.output_tokens;
+            usage.cached_input_tokens = mock_usage.cached_input_tokens;
+            usage.total_tokens = mock_usage.input_tokens + mock_usage.output_tokens;
+        }
+
+        Ok(VizierCompletion {
+            message_id: None,
+            choice: OneOrMany::many(choices).map_err(|_| {
+                VizierError("mock completion has neither text nor tool calls".into())
+            })?,
+            usage,
+            model: self.name.clone(),
+        })
+    }
+}
+
+// every string in the serialized message, so matching doesn't depend on the
+// message layout of the provider
+fn message_texts(message: &Message) -> Vec<String> {
+    fn collect(value: &serde_json::Value, res: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(s) => res.push(s.clone()),
+            serde_json::Value::Array(items) => items.iter().for_each(|item| collect(item, res)),
+            serde_json::Value::Object(map) => map.values().for_each(|item| collect(item, res)),
+            _ => {}
+        }
+    }
+
+    let mut res = vec![];
+    if let Ok(value) = serde_json::to_value(message) {
+        collect(&value, &mut res);
+    }
+
+    res
+}
+
+#[async_trait::async_trait]
+impl VizierModelTrait for MockModel {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn completion(
+        &self,
+        message: Message,
+        _history: Vec<Message>,
+        _tools: Vec<ToolDefinition>,
+    ) -> Result<VizierCompletion> {
+        let completion = self.next(&message)?;
+
+        self.to_completion(completion)
+    }
+
+    async fn stream_completion(
+        &self,
+        message: Message,
+        _history: Vec<Message>,
+        _tools: Vec<ToolDefinition>,
+        delta: flume::Sender<String>,
+    ) -> Result<VizierCompletion> {
+        let completion = self.next(&message)?;
+
+        if let Some(text) = &completion.text {
+            for chunk in text.split_inclusive(' ') {
+                let _ = delta.send(chunk.to_string());
+            }
+        }
+
+        self.to_completion(completion)
+    }
+}
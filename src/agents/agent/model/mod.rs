@@ -1,20 +1,36 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use futures::StreamExt;
 use rig::{
     OneOrMany,
-    completion::{CompletionModel, ToolDefinition, Usage},
+    completion::{CompletionModel, GetTokenUsage, ToolDefinition, Usage},
     message::{AssistantContent, Message},
     providers::{anthropic, deepseek, gemini, ollama, openai, openrouter},
+    streaming::StreamedAssistantContent,
 };
 
 use crate::{
-    config::provider::ProviderVariant, dependencies::VizierDependencies, error::VizierError,
+    agents::agent::model::{compatible::CompatibleModel, fallback::FallbackModel, mock::MockModel},
+    config::{agent::ModelFallbackConfig, provider::ProviderVariant},
+    dependencies::VizierDependencies,
+    error::VizierError,
     schema::AgentId,
 };
 
+mod compatible;
+mod fallback;
+mod mock;
 mod provider;
 
+pub struct VizierCompletion {
+    pub message_id: Option<String>,
+    pub choice: OneOrMany<AssistantContent>,
+    pub usage: Usage,
+    /// `provider/model` that produced this completion
+    pub model: String,
+}
+
 #[derive(Clone)]
 pub struct VizierModel(Arc<Box<dyn VizierModelTrait + Sync + Send + 'static>>);
 
@@ -30,24 +46,70 @@ impl VizierModel {
             .get(&agent_id)
             .ok_or(VizierError("agent not found".into()))?;
 
-        Ok(match agent_config.provider {
+        let chain = std::iter::once(ModelFallbackConfig {
+            provider: agent_config.provider.clone(),
+            model: agent_config.model.clone(),
+            context_window: agent_config.context.context_window,
+        })
+        .chain(agent_config.fallbacks.iter().cloned());
+
+        let mut models = vec![];
+        for model_config in chain {
+            models.push(
+                Self::from_provider(
+                    agent_id.clone(),
+                    model_config.provider,
+                    model_config.model,
+                    deps.clone(),
+                )
+                .await?,
+            );
+        }
+
+        Ok(Self::build(FallbackModel::new(
+            models,
+            agent_config.retry.clone(),
+        )))
+    }
+
+    /// a single model without fallbacks
+    pub async fn single(
+        agent_id: AgentId,
+        config: ModelFallbackConfig,
+        deps: VizierDependencies,
+    ) -> Result<Self> {
+        Self::from_provider(agent_id, config.provider, config.model, deps).await
+    }
+
+    async fn from_provider(
+        agent_id: AgentId,
+        provider: ProviderVariant,
+        model: String,
+        deps: VizierDependencies,
+    ) -> Result<Self> {
+        Ok(match provider {
             ProviderVariant::ollama => {
-                Self::build(VizierModelImpl::<ollama::Client>::build(agent_id, deps).await?)
+                Self::build(VizierModelImpl::<ollama::Client>::build(agent_id, model, deps).await?)
             }
             ProviderVariant::openai => {
-                Self::build(VizierModelImpl::<openai::Client>::build(agent_id, deps).await?)
-            }
-            ProviderVariant::anthropic => {
-                Self::build(VizierModelImpl::<anthropic::Client>::build(agent_id, deps).await?)
-            }
-            ProviderVariant::openrouter => {
-                Self::build(VizierModelImpl::<openrouter::Client>::build(agent_id, deps).await?)
+                Self::build(VizierModelImpl::<openai::Client>::build(agent_id, model, deps).await?)
             }
+            ProviderVariant::anthropic => Self::build(
+                VizierModelImpl::<anthropic::Client>::build(agent_id, model, deps).await?,
+            ),
+            ProviderVariant::openrouter => Self::build(
+                VizierModelImpl::<openrouter::Client>::build(agent_id, model, deps).await?,
+            ),
             ProviderVariant::gemini => {
-                Self::build(VizierModelImpl::<gemini::Client>::build(agent_id, deps).await?)
+                Self::build(VizierModelImpl::<gemini::Client>::build(agent_id, model, deps).await?)
             }
-            ProviderVariant::deepseek => {
-                Self::build(VizierModelImpl::<deepseek::Client>::build(agent_id, deps).await?)
+            ProviderVariant::deepseek => Self::build(
+                VizierModelImpl::<deepseek::Client>::build(agent_id, model, deps).await?,
+            ),
+            ProviderVariant::mock => Self::build(MockModel::load(model)?),
+            ProviderVariant::compatible(name) => {
+                let config = deps.config.providers.compatible.get(&name);
+                Self::build(CompatibleModel::build(name.clone(), model, config)?)
             }
         })
     }
@@ -55,14 +117,30 @@ impl VizierModel {
 
 #[async_trait::async_trait]
 impl VizierModelTrait for VizierModel {
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
     async fn completion(
         &self,
         message: Message,
         history: Vec<Message>,
         tools: Vec<ToolDefinition>,
-    ) -> Result<(Option<String>, OneOrMany<AssistantContent>, Usage)> {
+    ) -> Result<VizierCompletion> {
         self.0.completion(message, history, tools).await
     }
+
+    async fn stream_completion(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        delta: flume::Sender<String>,
+    ) -> Result<VizierCompletion> {
+        self.0
+            .stream_completion(message, history, tools, delta)
+            .await
+    }
 }
 
 #[async_trait::async_trait]
@@ -70,46 +148,62 @@ pub trait VizierModelBuilder<Client>
 where
     Client: rig::client::CompletionClient + Send + Sync,
 {
-    async fn init_client(agent_id: AgentId, deps: VizierDependencies) -> Result<Client>;
+    const PROVIDER: ProviderVariant;
 
-    async fn build(agent_id: AgentId, deps: VizierDependencies) -> Result<VizierModelImpl<Client>> {
-        let model = &deps
-            .config
-            .agents
-            .get(&agent_id)
-            .ok_or(VizierError("agent not found".into()))?
-            .model;
+    async fn init_client(agent_id: AgentId, deps: VizierDependencies) -> Result<Client>;
 
+    async fn build(
+        agent_id: AgentId,
+        model: String,
+        deps: VizierDependencies,
+    ) -> Result<VizierModelImpl<Client>> {
+        let name = format!("{:?}/{}", Self::PROVIDER, model);
         let model = Self::init_client(agent_id, deps.clone())
             .await?
-            .completion_model(model);
+            .completion_model(&model);
 
-        Ok(VizierModelImpl::<Client>(model))
+        Ok(VizierModelImpl::<Client>(model, name))
     }
 }
 
 #[async_trait::async_trait]
 pub trait VizierModelTrait {
+    fn name(&self) -> String;
+
     async fn completion(
         &self,
         message: Message,
         history: Vec<Message>,
         tools: Vec<ToolDefinition>,
-    ) -> Result<(Option<String>, OneOrMany<AssistantContent>, Usage)>;
+    ) -> Result<VizierCompletion>;
+
+    /// same as `completion`, but every text chunk is sent to `delta` as soon as
+    /// the provider yields it
+    async fn stream_completion(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        delta: flume::Sender<String>,
+    ) -> Result<VizierCompletion>;
 }
 
-pub struct VizierModelImpl<T>(T::CompletionModel)
+pub struct VizierModelImpl<T>(T::CompletionModel, String)
 where
     T: rig::client::CompletionClient;
 
 #[async_trait::async_trait]
 impl<T: rig::client::CompletionClient> VizierModelTrait for VizierModelImpl<T> {
+    fn name(&self) -> String {
+        self.1.clone()
+    }
+
     async fn completion(
         &self,
         message: Message,
         history: Vec<Message>,
         tools: Vec<ToolDefinition>,
-    ) -> Result<(Option<String>, OneOrMany<AssistantContent>, Usage)> {
+    ) -> Result<VizierCompletion> {
         let request = self
             .0
             .completion_request(message)
@@ -119,6 +213,47 @@ impl<T: rig::client::CompletionClient> VizierModelTrait for VizierModelImpl<T> {
 
         let response = self.0.completion(request).await?;
 
-        Ok((response.message_id, response.choice, response.usage))
+        Ok(VizierCompletion {
+            message_id: response.message_id,
+            choice: response.choice,
+            usage: response.usage,
+            model: self.name(),
+        })
+    }
+
+    async fn stream_completion(
+        &self,
+        message: Message,
+        history: Vec<Message>,
+        tools: Vec<ToolDefinition>,
+        delta: flume::Sender<String>,
+    ) -> Result<VizierCompletion> {
+        let request = self
+            .0
+            .completion_request(message)
+            .messages(history)
+            .tools(tools)
+            .build();
+
+        let mut stream = self.0.stream(request).await?;
+        while let Some(chunk) = stream.next().await {
+            if let StreamedAssistantContent::Text(text) = chunk? {
+                // the receiver may be gone already, the aggregated response still matters
+                let _ = delta.send(text.text);
+            }
+        }
+
+        let usage = stream
+            .response
+            .as_ref()
+            .and_then(|response| response.token_usage())
+            .unwrap_or(Usage::new());
+
+        Ok(VizierCompletion {
+            message_id: None,
+            choice: stream.choice.clone(),
+            usage,
+            model: self.name(),
+        })
     }
 }
@@ -6,11 +6,14 @@ use rig::{
 
 use crate::{
     agents::agent::model::{VizierModelBuilder, VizierModelImpl},
+    config::provider::ProviderVariant,
     dependencies::VizierDependencies,
 };
 
 #[async_trait::async_trait]
 impl VizierModelBuilder<ollama::Client> for VizierModelImpl<ollama::Client> {
+    const PROVIDER: ProviderVariant = ProviderVariant::ollama;
+
     async fn init_client(_agent_id: String, deps: VizierDependencies) -> Result<ollama::Client> {
         let base_url = deps.config.providers.ollama.clone().unwrap().base_url;
 
@@ -25,6 +28,8 @@ impl VizierModelBuilder<ollama::Client> for VizierModelImpl<ollama::Client> {
 
 #[async_trait::async_trait]
 impl VizierModelBuilder<openrouter::Client> for VizierModelImpl<openrouter::Client> {
+    const PROVIDER: ProviderVariant = ProviderVariant::openrouter;
+
     async fn init_client(
         _agent_id: String,
         deps: VizierDependencies,
@@ -38,6 +43,8 @@ impl VizierModelBuilder<openrouter::Client> for VizierModelImpl<openrouter::Clie
 
 #[async_trait::async_trait]
 impl VizierModelBuilder<deepseek::Client> for VizierModelImpl<deepseek::Client> {
+    const PROVIDER: ProviderVariant = ProviderVariant::deepseek;
+
     async fn init_client(_agent_id: String, deps: VizierDependencies) -> Result<deepseek::Client> {
         let client: deepseek::Client =
             deepseek::Client::new(deps.config.providers.deepseek.clone().unwrap().api_key)?;
@@ -48,6 +55,8 @@ impl VizierModelBuilder<deepseek::Client> for VizierModelImpl<deepseek::Client>
 
 #[async_trait::async_trait]
 impl VizierModelBuilder<anthropic::Client> for VizierModelImpl<anthropic::Client> {
+    const PROVIDER: ProviderVariant = ProviderVariant::anthropic;
+
     async fn init_client(_agent_id: String, deps: VizierDependencies) -> Result<anthropic::Client> {
         let client: anthropic::Client =
             anthropic::Client::new(deps.config.providers.anthropic.clone().unwrap().api_key)?;
@@ -58,6 +67,8 @@ impl VizierModelBuilder<anthropic::Client> for VizierModelImpl<anthropic::Client
 
 #[async_trait::async_trait]
 impl VizierModelBuilder<openai::Client> for VizierModelImpl<openai::Client> {
+    const PROVIDER: ProviderVariant = ProviderVariant::openai;
+
     async fn init_client(_agent_id: String, deps: VizierDependencies) -> Result<openai::Client> {
         let client: openai::Client =
             if let Some(base_url) = deps.config.providers.openai.clone().unwrap().base_url {
@@ -76,6 +87,8 @@ impl VizierModelBuilder<openai::Client> for VizierModelImpl<openai::Client> {
 
 #[async_trait::async_trait]
 impl VizierModelBuilder<gemini::Client> for VizierModelImpl<gemini::Client> {
+    const PROVIDER: ProviderVariant = ProviderVariant::gemini;
+
     async fn init_client(_agent_id: String, deps: VizierDependencies) -> Result<gemini::Client> {
         let client: gemini::Client =
             gemini::Client::new(deps.config.providers.gemini.clone().unwrap().api_key)?;
@@ -0,0 +1,82 @@
+use crate::{
+    agents::{agent::VizierAgent, tools::matches_tool},
+    schema::{VizierChannelId, VizierRequest},
+};
+
+/// who a request comes from, ordered from the least to the most trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SenderTier {
+    Everyone,
+    Trusted,
+    Primary,
+}
+
+/// the discord or telegram id of whoever sent the request, none for the
+/// channels that only the primary user reaches
+pub fn sender_id(req: &VizierRequest) -> Option<String> {
+    req.metadata["discord_user_id"]
+        .as_str()
+        .or(req.metadata["telegram_user_id"].as_str())
+        .map(|id| id.to_string())
+}
+
+impl VizierAgent {
+    /// discord and telegram senders are matched against the primary user and
+    /// the trusted users, a message without a sender id is from anyone. only
+    /// the channels the primary user drives (web ui, tasks, heartbeats,
+    /// dreams) come from the primary user, other agents and subagents get the
+    /// least trust
+    pub fn sender_tier(&self, channel: &VizierChannelId, req: &VizierRequest) -> SenderTier {
+        let sender = match channel {
+            VizierChannelId::DiscordChanel(_) => {
+                let Some(id) = req.metadata["discord_user_id"].as_str() else {
+                    return SenderTier::Everyone;
+                };
+                if !self.primary_user.discord_id.is_empty() && id == self.primary_user.discord_id {
+                    return SenderTier::Primary;
+                }
+                id
+            }
+            VizierChannelId::TelegramChannel(_) => {
+                let Some(id) = req.metadata["telegram_user_id"].as_str() else {
+                    return SenderTier::Everyone;
+                };
+                // usernames are optional and can change hands, ids can't
+                if !self.primary_user.telegram_id.is_empty() && id == self.primary_user.telegram_id
+                {
+                    return SenderTier::Primary;
+                }
+                id
+            }
+            VizierChannelId::HTTP(_)
+            | VizierChannelId::Task(_, _)
+            | VizierChannelId::Heartbeat(_)
+            | VizierChannelId::Dream(_) => return SenderTier::Primary,
+            VizierChannelId::InterAgent(_)
+            | VizierChannelId::Subagent
+            | VizierChannelId::System => return SenderTier::Everyone,
+        };
+
+        match &self.config.tools.permissions {
+            Some(permissions) if permissions.trusted_users.iter().any(|user| user == sender) => {
+                SenderTier::Trusted
+            }
+            _ => SenderTier::Everyone,
+        }
+    }
+
+    pub(super) fn is_permitted(&self, function_name: &str, tier: SenderTier) -> bool {
+        let Some(permissions) = &self.config.tools.permissions else {
+            return true;
+        };
+
+        match tier {
+            SenderTier::Primary => true,
+            SenderTier::Trusted => {
+                matches_tool(&permissions.trusted, function_name)
+                    || matches_tool(&permissions.everyone, function_name)
+            }
+            SenderTier::Everyone => matches_tool(&permissions.everyone, function_name),
+        }
+    }
+}
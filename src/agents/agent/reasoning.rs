@@ -0,0 +1,46 @@
+use rig::{OneOrMany, message::AssistantContent};
+
+/// splits the `<think>` block some models (e.g. deepseek-r1 on ollama) inline
+/// in their text, the opening tag is often missing
+pub fn split_think_tags(text: &str) -> (Option<String>, String) {
+    match text.split_once("</think>") {
+        Some((thinking, answer)) => {
+            let thinking = thinking.trim().trim_start_matches("<think>").trim();
+            let thinking = (!thinking.is_empty()).then(|| thinking.to_string());
+
+            (thinking, answer.trim().to_string())
+        }
+        None => (None, text.to_string()),
+    }
+}
+
+/// reasoning of a completion, both the provider's reasoning blocks and the
+/// inlined `<think>` tags
+pub fn reasoning_of(choices: &OneOrMany<AssistantContent>) -> Option<String> {
+    let reasoning = choices
+        .iter()
+        .filter_map(|item| match item {
+            AssistantContent::Reasoning(reasoning) => Some(reasoning.display_text()),
+            AssistantContent::Text(text) => split_think_tags(&text.text).0,
+            _ => None,
+        })
+        .filter(|reasoning| !reasoning.trim().is_empty())
+        .collect::<Vec<_>>();
+
+    (!reasoning.is_empty()).then(|| reasoning.join("\n\n"))
+}
+
+/// answer of a completion without the reasoning
+pub fn text_of(choices: &[&AssistantContent]) -> String {
+    choices
+        .iter()
+        .filter_map(|item| {
+            if let AssistantContent::Text(text) = item {
+                Some(split_think_tags(&text.text).1)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rig::message::Message;
+use serde_json::Value;
+
+use crate::{
+    agents::{
+        agent::{SenderTier, VizierAgent},
+        hook::VizierSessionHooks,
+    },
+    error::VizierError,
+    schema::{VizierAttachment, VizierResponseStats},
+};
+
+// how many times a mismatching answer is sent back to the model
+const MAX_SCHEMA_RETRIES: usize = 2;
+
+/// start of the error of an answer that never matched the schema
+pub const SCHEMA_MISMATCH: &str = "answer doesn't match the response schema";
+
+pub fn schema_instruction(schema: &Value) -> Message {
+    Message::system(format!(
+        "# Response Format\nyour final answer must be a single JSON value matching the JSON Schema below, without any prose or code fences around it.\n```json\n{}\n```",
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    ))
+}
+
+/// JSON value of an answer, models tend to wrap it in code fences anyway
+fn extract_json(output: &str) -> Result<Value, String> {
+    let trimmed = output.trim();
+    let trimmed = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(trimmed);
+
+    serde_json::from_str(trimmed.trim()).map_err(|err| format!("not a valid JSON: {}", err))
+}
+
+fn add_stats(total: &mut VizierResponseStats, stats: VizierResponseStats) {
+    total.add_usage(&stats);
+    total.model = stats.model;
+    total.reasoning = match (total.reasoning.take(), stats.reasoning) {
+        (Some(a), Some(b)) => Some(format!("{}\n\n{}", a, b)),
+        (a, b) => a.or(b),
+    };
+}
+
+impl VizierAgent {
+    /// prompts until the answer matches the schema, validation errors are fed
+    /// back to the model so it can correct itself
+    pub(super) async fn structured_prompt(
+        &self,
+        schema: &Value,
+        message: Message,
+        history: Vec<Message>,
+        hooks: Option<Arc<VizierSessionHooks>>,
+        tier: SenderTier,
+    ) -> Result<(Value, VizierResponseStats, Vec<VizierAttachment>)> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|err| VizierError(format!("invalid response schema: {}", err)))?;
+
+        let mut message = message;
+        let mut history = history;
+        let mut total: Option<VizierResponseStats> = None;
+        let mut attachments = vec![];
+        let mut retries = 0;
+        loop {
+            let (output, stats, turn_attachments) = self
+                .prompt(
+                    message.clone(),
+                    history.clone(),
+                    0,
+                    hooks.clone(),
+                    false,
+                    tier,
+                )
+                .await?;
+            attachments.extend(turn_attachments);
+            match total.as_mut() {
+                Some(total) => add_stats(total, stats),
+                None => total = Some(stats),
+            }
+
+            let errors = match extract_json(&output) {
+                Ok(value) => {
+                    let errors = validator
+                        .iter_errors(&value)
+                        .map(|err| err.to_string())
+                        .collect::<Vec<_>>();
+                    if errors.is_empty() {
+                        return Ok((value, total.unwrap(), attachments));
+                    }
+
+                    errors
+                }
+                Err(err) => vec![err],
+            };
+
+            if retries >= MAX_SCHEMA_RETRIES {
+                return Err(
+                    VizierError(format!("{}: {}", SCHEMA_MISMATCH, errors.join("; "))).into(),
+                );
+            }
+            retries += 1;
+
+            history.push(message);
+            history.push(Message::assistant(output));
+            message = Message::user(format!(
+                "your answer doesn't match the response schema:\n- {}\n\nreply again with only the corrected JSON.",
+                errors.join("\n- ")
+            ));
+        }
+    }
+}
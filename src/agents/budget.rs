@@ -0,0 +1,57 @@
+use anyhow::Result;
+use chrono::{Datelike, Utc};
+
+use crate::{
+    config::agent::BudgetConfig,
+    storage::{VizierStorage, history::HistoryStorage},
+};
+
+/// why the agent can't spend any more right now, if it can't
+pub async fn exceeded_budget(
+    storage: &VizierStorage,
+    agent_id: &str,
+    budget: &BudgetConfig,
+) -> Result<Option<String>> {
+    if budget.daily.is_none() && budget.monthly.is_none() {
+        return Ok(None);
+    }
+
+    // one aggregate covers both periods, the daily spend is today's entry
+    let today = Utc::now().date_naive();
+    let since = match budget.monthly {
+        Some(_) => today.with_day(1).unwrap(),
+        None => today,
+    };
+    let usage = storage
+        .aggregate_usage(
+            agent_id,
+            Some(since.and_hms_opt(0, 0, 0).unwrap().and_utc()),
+            None,
+        )
+        .await?;
+    let spent_today = usage
+        .by_day
+        .iter()
+        .find(|day| day.date == today)
+        .map(|day| day.cost)
+        .unwrap_or(0.0);
+
+    let limits = [
+        ("daily", budget.daily, spent_today),
+        ("monthly", budget.monthly, usage.summary.total_cost),
+    ];
+    for (period, limit, spent) in limits {
+        let Some(limit) = limit else {
+            continue;
+        };
+
+        if spent >= limit {
+            return Ok(Some(format!(
+                "{} budget of ${:.2} is used up (${:.2} spent)",
+                period, limit, spent
+            )));
+        }
+    }
+
+    Ok(None)
+}
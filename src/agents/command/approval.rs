@@ -0,0 +1,54 @@
+use anyhow::Result;
+
+use crate::{
+    agents::command::{CommandContext, VizierCommand},
+    error::VizierError,
+};
+
+/// answers an approval request of a paused tool call
+pub struct ApprovalCommand {
+    pub approve: bool,
+}
+
+#[async_trait::async_trait]
+impl VizierCommand for ApprovalCommand {
+    fn name(&self) -> String {
+        if self.approve { "approve" } else { "deny" }.into()
+    }
+
+    fn description(&self) -> String {
+        if self.approve {
+            "let a tool call waiting for approval run"
+        } else {
+            "reject a tool call waiting for approval"
+        }
+        .into()
+    }
+
+    fn usage(&self) -> String {
+        "<approval_id>".into()
+    }
+
+    async fn run(&self, ctx: &CommandContext, args: Vec<String>) -> Result<String> {
+        let id = args.first().ok_or(VizierError(format!(
+            "usage: /{} {}",
+            self.name(),
+            self.usage()
+        )))?;
+
+        let name = ctx.approvals.resolve(
+            &ctx.session,
+            id,
+            self.approve,
+            ctx.sender.as_deref(),
+            ctx.tier,
+            &ctx.user,
+        )?;
+
+        Ok(format!(
+            "`{}` {}",
+            name,
+            if self.approve { "approved" } else { "denied" }
+        ))
+    }
+}
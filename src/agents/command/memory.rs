@@ -0,0 +1,72 @@
+use anyhow::Result;
+
+use crate::{
+    agents::{
+        agent::SenderTier,
+        command::{CommandContext, VizierCommand},
+    },
+    error::VizierError,
+    storage::memory::MemoryStorage,
+};
+
+pub struct MemoryCommand;
+
+#[async_trait::async_trait]
+impl VizierCommand for MemoryCommand {
+    fn name(&self) -> String {
+        "memory".into()
+    }
+
+    fn description(&self) -> String {
+        "list the agent's memories, or delete one".into()
+    }
+
+    fn usage(&self) -> String {
+        "[delete <slug>]".into()
+    }
+
+    fn tier(&self, args: &[String]) -> SenderTier {
+        match args.first().map(|arg| arg.as_str()) {
+            Some("delete") => SenderTier::Primary,
+            _ => SenderTier::Everyone,
+        }
+    }
+
+    async fn run(&self, ctx: &CommandContext, args: Vec<String>) -> Result<String> {
+        let agent_id = ctx.session.0.clone();
+
+        match args.first().map(|arg| arg.as_str()) {
+            None | Some("list") => {
+                let memories = ctx.storage.get_all_agent_memory(agent_id).await?;
+                if memories.is_empty() {
+                    return Ok("no memory yet".into());
+                }
+
+                Ok(memories
+                    .iter()
+                    .map(|memory| format!("- **{}**: {}", memory.slug, memory.title))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            Some("delete") => {
+                let slug = args
+                    .get(1)
+                    .ok_or(VizierError("usage: /memory delete <slug>".into()))?;
+
+                ctx.storage
+                    .get_memory_detail(agent_id.clone(), slug.clone())
+                    .await?
+                    .ok_or(VizierError(format!("memory {} not found", slug)))?;
+                ctx.storage.delete_memory(agent_id, slug.clone()).await?;
+
+                Ok(format!("memory **{}** deleted", slug))
+            }
+            Some(arg) => Err(VizierError(format!(
+                "unknown argument `{}`, usage: /memory {}",
+                arg,
+                self.usage()
+            ))
+            .into()),
+        }
+    }
+}
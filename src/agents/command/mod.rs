@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::{
+    agents::{
+        agent::SenderTier,
+        command::{
+            approval::ApprovalCommand,
+            memory::MemoryCommand,
+            task::TaskCommand,
+            thinking::ThinkingCommand,
+            topic::{NewTopic, SwitchTopic},
+            usage::UsageCommand,
+        },
+        hook::approval::PendingApprovals,
+    },
+    config::agent::AgentConfig,
+    error::VizierError,
+    schema::VizierSession,
+    storage::VizierStorage,
+};
+
+mod approval;
+mod memory;
+mod task;
+mod thinking;
+mod topic;
+mod usage;
+
+pub struct CommandContext {
+    pub session: VizierSession,
+    pub agent_config: AgentConfig,
+    pub storage: Arc<VizierStorage>,
+    pub approvals: Arc<PendingApprovals>,
+    pub tier: SenderTier,
+    /// discord or telegram id of the sender, none for the primary user's channels
+    pub sender: Option<String>,
+    pub user: String,
+}
+
+#[async_trait::async_trait]
+pub trait VizierCommand {
+    fn name(&self) -> String;
+
+    fn description(&self) -> String;
+
+    /// argument synopsis shown in help, e.g. `[topic_id]`
+    fn usage(&self) -> String {
+        String::new()
+    }
+
+    /// the least trusted sender that may run the command with these args
+    fn tier(&self, _args: &[String]) -> SenderTier {
+        SenderTier::Everyone
+    }
+
+    async fn run(&self, ctx: &CommandContext, args: Vec<String>) -> Result<String>;
+}
+
+type VizierCommandDef = Arc<Box<dyn VizierCommand + Send + Sync + 'static>>;
+
+/// commands shared by every channel, channels forward `/name args` as
+/// `VizierRequestContent::Command` and get the output back as a message
+#[derive(Clone)]
+pub struct VizierCommands {
+    commands: Vec<VizierCommandDef>,
+}
+
+impl Default for VizierCommands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VizierCommands {
+    pub fn new() -> Self {
+        Self { commands: vec![] }
+            .command(Ping)
+            .command(NewTopic)
+            .command(SwitchTopic)
+            .command(UsageCommand)
+            .command(MemoryCommand)
+            .command(TaskCommand)
+            .command(ThinkingCommand)
+            .command(ApprovalCommand { approve: true })
+            .command(ApprovalCommand { approve: false })
+    }
+
+    fn command<Command: VizierCommand + Send + Sync + 'static>(mut self, command: Command) -> Self {
+        self.commands.push(Arc::new(Box::new(command)));
+
+        self
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &VizierCommandDef> {
+        self.commands.iter()
+    }
+
+    pub fn is_command(&self, raw: &str) -> bool {
+        let (name, _) = parse_command(raw);
+        name == "help" || self.get(&name).is_some()
+    }
+
+    fn get(&self, name: &str) -> Option<&VizierCommandDef> {
+        self.commands.iter().find(|command| command.name() == name)
+    }
+
+    pub fn help(&self) -> String {
+        let mut res = vec!["**Commands**".to_string()];
+        for command in &self.commands {
+            let usage = command.usage();
+            res.push(format!(
+                "`/{}{}` - {}",
+                command.name(),
+                if usage.is_empty() {
+                    "".to_string()
+                } else {
+                    format!(" {}", usage)
+                },
+                command.description()
+            ));
+        }
+        res.push("`/help` - show this message".into());
+
+        res.join("\n")
+    }
+
+    pub async fn execute(&self, ctx: &CommandContext, raw: &str) -> Result<String> {
+        let (name, args) = parse_command(raw);
+        if name == "help" {
+            return Ok(self.help());
+        }
+
+        let command = self.get(&name).ok_or(VizierError(format!(
+            "unknown command `/{}`, try `/help`",
+            name
+        )))?;
+
+        if ctx.tier < command.tier(&args) {
+            return Err(VizierError(format!("you are not allowed to run `/{}`", name)).into());
+        }
+
+        command.run(ctx, args).await
+    }
+}
+
+/// `/name@bot arg1 arg2` -> (`name`, [`arg1`, `arg2`])
+pub fn parse_command(raw: &str) -> (String, Vec<String>) {
+    let mut parts = raw.trim().trim_start_matches('/').split_whitespace();
+    let name = parts
+        .next()
+        .unwrap_or_default()
+        .split('@')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    (name, parts.map(|part| part.to_string()).collect())
+}
+
+struct Ping;
+
+#[async_trait::async_trait]
+impl VizierCommand for Ping {
+    fn name(&self) -> String {
+        "ping".into()
+    }
+
+    fn description(&self) -> String {
+        "a simple ping".into()
+    }
+
+    async fn run(&self, _ctx: &CommandContext, _args: Vec<String>) -> Result<String> {
+        Ok("Pong!".into())
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+
+use crate::{
+    agents::command::{CommandContext, VizierCommand},
+    schema::TaskSchedule,
+    storage::task::TaskStorage,
+};
+
+pub struct TaskCommand;
+
+#[async_trait::async_trait]
+impl VizierCommand for TaskCommand {
+    fn name(&self) -> String {
+        "tasks".into()
+    }
+
+    fn description(&self) -> String {
+        "list the agent's scheduled tasks".into()
+    }
+
+    async fn run(&self, ctx: &CommandContext, _args: Vec<String>) -> Result<String> {
+        let tasks = ctx
+            .storage
+            .get_task_list(Some(ctx.session.0.clone()), None)
+            .await?;
+
+        if tasks.is_empty() {
+            return Ok("no task yet".into());
+        }
+
+        Ok(tasks
+            .iter()
+            .map(|task| {
+                format!(
+                    "- **{}**: {} ({}{})",
+                    task.slug,
+                    task.title,
+                    match &task.schedule {
+                        TaskSchedule::CronTask(cron) => format!("cron `{}`", cron),
+                        TaskSchedule::OneTimeTask(at) => format!("once at {}", at.to_rfc3339()),
+                    },
+                    if task.is_active { "" } else { ", inactive" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
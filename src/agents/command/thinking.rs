@@ -0,0 +1,67 @@
+use anyhow::Result;
+
+use crate::{
+    agents::{
+        agent::SenderTier,
+        command::{CommandContext, VizierCommand},
+    },
+    error::VizierError,
+    storage::state::StateStorage,
+};
+
+pub struct ThinkingCommand;
+
+#[async_trait::async_trait]
+impl VizierCommand for ThinkingCommand {
+    fn name(&self) -> String {
+        "thinking".into()
+    }
+
+    fn description(&self) -> String {
+        "show or hide the agent's thoughts in this session".into()
+    }
+
+    fn usage(&self) -> String {
+        "[on|off]".into()
+    }
+
+    // the thoughts may quote the system prompt and tool results
+    fn tier(&self, _args: &[String]) -> SenderTier {
+        SenderTier::Trusted
+    }
+
+    async fn run(&self, ctx: &CommandContext, args: Vec<String>) -> Result<String> {
+        let mut settings = ctx
+            .storage
+            .get_session_settings(ctx.session.clone())
+            .await?;
+        let current = settings
+            .show_thinking
+            .or(ctx.agent_config.show_thinking)
+            .unwrap_or(false);
+
+        let show_thinking = match args.first().map(|arg| arg.as_str()) {
+            None => !current,
+            Some("on") => true,
+            Some("off") => false,
+            Some(arg) => {
+                return Err(VizierError(format!(
+                    "unknown argument `{}`, usage: /thinking {}",
+                    arg,
+                    self.usage()
+                ))
+                .into());
+            }
+        };
+
+        settings.show_thinking = Some(show_thinking);
+        ctx.storage
+            .save_session_settings(ctx.session.clone(), settings)
+            .await?;
+
+        Ok(format!(
+            "thinking is now **{}**",
+            if show_thinking { "shown" } else { "hidden" }
+        ))
+    }
+}
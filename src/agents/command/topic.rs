@@ -0,0 +1,115 @@
+use anyhow::Result;
+
+use crate::{
+    agents::command::{CommandContext, VizierCommand},
+    error::VizierError,
+    schema::{TopicId, VizierChannelId},
+    storage::{session::SessionStorage, state::StateStorage},
+};
+
+/// websocket clients pick the topic in the url, there's no active topic to set
+fn unsupported(ctx: &CommandContext) -> Option<String> {
+    match ctx.session.1 {
+        VizierChannelId::HTTP(_) => {
+            Some("sessions can't be switched over the websocket, open the topic instead".into())
+        }
+        _ => None,
+    }
+}
+
+pub struct NewTopic;
+
+#[async_trait::async_trait]
+impl VizierCommand for NewTopic {
+    fn name(&self) -> String {
+        "new".into()
+    }
+
+    fn description(&self) -> String {
+        "create fresh new session".into()
+    }
+
+    async fn run(&self, ctx: &CommandContext, _args: Vec<String>) -> Result<String> {
+        if let Some(reply) = unsupported(ctx) {
+            return Ok(reply);
+        }
+
+        let topic_id = nanoid::nanoid!(10);
+
+        ctx.storage
+            .set_active_topic(
+                ctx.session.0.clone(),
+                ctx.session.1.clone(),
+                Some(topic_id.clone()),
+            )
+            .await?;
+
+        Ok(format!("switch to new session: **{}**", topic_id))
+    }
+}
+
+pub struct SwitchTopic;
+
+#[async_trait::async_trait]
+impl VizierCommand for SwitchTopic {
+    fn name(&self) -> String {
+        "session".into()
+    }
+
+    fn description(&self) -> String {
+        "list sessions, or switch to one".into()
+    }
+
+    fn usage(&self) -> String {
+        "[topic_id]".into()
+    }
+
+    async fn run(&self, ctx: &CommandContext, args: Vec<String>) -> Result<String> {
+        if let Some(reply) = unsupported(ctx) {
+            return Ok(reply);
+        }
+
+        let agent_id = ctx.session.0.clone();
+        let channel = ctx.session.1.clone();
+
+        let Some(raw_topic_id) = args.first() else {
+            let sessions = ctx
+                .storage
+                .get_session_list(agent_id, Some(channel))
+                .await?;
+
+            if sessions.is_empty() {
+                return Ok("no session yet".into());
+            }
+
+            return Ok(sessions
+                .iter()
+                .map(|session| {
+                    format!(
+                        "topic_id: {}\ntitle: {}",
+                        session.topic.clone().unwrap_or("DEFAULT".into()),
+                        session.title.clone()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"));
+        };
+
+        let topic_id: Option<TopicId> = if raw_topic_id == "DEFAULT" {
+            None
+        } else {
+            Some(raw_topic_id.clone())
+        };
+
+        ctx.storage
+            .get_session_detail_by_topic(agent_id.clone(), channel.clone(), topic_id.clone())
+            .await?
+            .ok_or(VizierError("topic not found".into()))?;
+
+        ctx.storage
+            .set_active_topic(agent_id, channel, topic_id)
+            .await?;
+
+        Ok(format!("switch to session: **{}**", raw_topic_id))
+    }
+}
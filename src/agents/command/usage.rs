@@ -0,0 +1,86 @@
+use anyhow::Result;
+use chrono::{TimeDelta, Utc};
+
+use crate::{
+    agents::command::{CommandContext, VizierCommand},
+    error::VizierError,
+    storage::history::HistoryStorage,
+};
+
+// ten years, far enough back for any usage report
+const MAX_DAYS: u32 = 3650;
+
+/// a whole number of days between 1 and `MAX_DAYS`
+fn parse_days(raw: &str) -> Result<u32, VizierError> {
+    raw.parse::<u32>()
+        .ok()
+        .filter(|days| (1..=MAX_DAYS).contains(days))
+        .ok_or(VizierError(format!(
+            "invalid number of days: {}, expected 1 to {}",
+            raw, MAX_DAYS
+        )))
+}
+
+pub struct UsageCommand;
+
+#[async_trait::async_trait]
+impl VizierCommand for UsageCommand {
+    fn name(&self) -> String {
+        "usage".into()
+    }
+
+    fn description(&self) -> String {
+        "show token usage of the agent".into()
+    }
+
+    fn usage(&self) -> String {
+        "[days]".into()
+    }
+
+    async fn run(&self, ctx: &CommandContext, args: Vec<String>) -> Result<String> {
+        let days = args.first().map(String::as_str).map(parse_days).transpose()?;
+        let start = days
+            .map(|days| {
+                TimeDelta::try_days(days.into())
+                    .and_then(|delta| Utc::now().checked_sub_signed(delta))
+                    .ok_or(VizierError(format!("invalid number of days: {}", days)))
+            })
+            .transpose()?;
+
+        let stats = ctx
+            .storage
+            .aggregate_usage(&ctx.session.0, start, None)
+            .await?;
+
+        Ok(format!(
+            "**Usage{}**\nrequests: {}\ninput tokens: {} ({} cached)\noutput tokens: {}\ntotal tokens: {}\ncost: ${:.2}\naverage duration: {:.0}ms",
+            days.map(|days| format!(" (last {} days)", days))
+                .unwrap_or_default(),
+            stats.summary.total_requests,
+            stats.summary.total_input_tokens,
+            stats.summary.total_cached_input_tokens,
+            stats.summary.total_output_tokens,
+            stats.summary.total_tokens,
+            stats.summary.total_cost,
+            stats.summary.avg_duration_ms,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_days_within_range() {
+        assert_eq!(parse_days("7").unwrap(), 7);
+        assert_eq!(parse_days("3650").unwrap(), 3650);
+    }
+
+    #[test]
+    fn rejects_days_out_of_range() {
+        for raw in ["0", "-1", "3651", "100000000", "999999999999", "abc", ""] {
+            assert!(parse_days(raw).is_err(), "{} was accepted", raw);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use crate::{
+    agents::{
+        agent::{SenderTier, sender_id, turn_time_left},
+        hook::VizierSessionHook,
+        tools::matches_tool,
+    },
+    error::{VizierError, VizierToolDenied},
+    schema::{
+        VizierChannelId, VizierRequest, VizierResponse, VizierResponseContent, VizierSession,
+    },
+    transport::VizierTransport,
+};
+
+const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// turn time kept for the model to go on once a call is denied
+const APPROVAL_TURN_MARGIN: Duration = Duration::from_secs(30);
+
+/// whether the call may run, and who decided
+struct Decision {
+    approved: bool,
+    by: String,
+}
+
+struct PendingApproval {
+    session: VizierSession,
+    name: String,
+    /// who made the request that led to the call, none for the primary user
+    requester: Option<String>,
+    sender: oneshot::Sender<Decision>,
+}
+
+/// tool calls of an agent waiting for a human decision, keyed by approval id
+#[derive(Default)]
+pub struct PendingApprovals(Mutex<HashMap<String, PendingApproval>>);
+
+impl PendingApprovals {
+    fn register(
+        &self,
+        id: String,
+        session: VizierSession,
+        name: String,
+        requester: Option<String>,
+    ) -> oneshot::Receiver<Decision> {
+        let (sender, receiver) = oneshot::channel();
+        self.0.lock().unwrap().insert(
+            id,
+            PendingApproval {
+                session,
+                name,
+                requester,
+                sender,
+            },
+        );
+
+        receiver
+    }
+
+    fn cancel(&self, id: &str) {
+        self.0.lock().unwrap().remove(id);
+    }
+
+    /// answers a pending call, only from the channel it was asked on and by
+    /// the user who asked or the primary user. returns the tool name
+    pub fn resolve(
+        &self,
+        session: &VizierSession,
+        id: &str,
+        approved: bool,
+        sender: Option<&str>,
+        tier: SenderTier,
+        by: &str,
+    ) -> Result<String> {
+        let mut pending = self.0.lock().unwrap();
+        let approval = match pending.get(id) {
+            Some(approval)
+                if approval.session.0 == session.0 && approval.session.1 == session.1 =>
+            {
+                approval
+            }
+            _ => return Err(VizierError(format!("no pending approval `{}`", id)).into()),
+        };
+        if tier != SenderTier::Primary && approval.requester.as_deref() != sender {
+            return Err(VizierError(format!(
+                "only the user who asked or the primary user can answer `{}`",
+                approval.name
+            ))
+            .into());
+        }
+
+        let approval = pending.remove(id).unwrap();
+        // the turn might have been aborted in the meantime
+        approval
+            .sender
+            .send(Decision {
+                approved,
+                by: by.to_string(),
+            })
+            .map_err(|_| VizierError(format!("`{}` is no longer waiting", approval.name)))?;
+
+        Ok(approval.name)
+    }
+}
+
+/// pauses sensitive tool calls until the user approves them on the channel
+#[derive(Clone)]
+pub struct ApprovalHook {
+    transport: VizierTransport,
+    session: VizierSession,
+    approvals: Arc<PendingApprovals>,
+    tools: Vec<String>,
+    timeout: Duration,
+    /// sender of the latest request, who may answer for the calls it leads to
+    requester: Arc<Mutex<Option<String>>>,
+}
+
+impl ApprovalHook {
+    pub fn new(
+        transport: VizierTransport,
+        session: VizierSession,
+        approvals: Arc<PendingApprovals>,
+        tools: Vec<String>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            transport,
+            session,
+            approvals,
+            tools,
+            timeout: timeout.unwrap_or(DEFAULT_APPROVAL_TIMEOUT),
+            requester: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl VizierSessionHook for ApprovalHook {
+    async fn on_request(&self, req: VizierRequest) -> Result<VizierRequest> {
+        *self.requester.lock().unwrap() = sender_id(&req);
+
+        Ok(req)
+    }
+
+    async fn on_tool_call(&self, function_name: String, args: String) -> Result<(String, String)> {
+        if !matches_tool(&self.tools, &function_name) {
+            return Ok((function_name, args));
+        }
+
+        // nobody is around to answer on background sessions
+        if !matches!(
+            self.session.1,
+            VizierChannelId::DiscordChanel(_)
+                | VizierChannelId::TelegramChannel(_)
+                | VizierChannelId::HTTP(_)
+        ) {
+            return Err(VizierToolDenied(format!(
+                "the `{}` call needs a user approval, which can't be asked from this session",
+                function_name
+            ))
+            .into());
+        }
+
+        // the wait counts against the turn, the model has to be able to go on
+        let wait = match turn_time_left() {
+            Some(left) => self.timeout.min(left.saturating_sub(APPROVAL_TURN_MARGIN)),
+            None => self.timeout,
+        };
+        if wait.is_zero() {
+            return Err(VizierToolDenied(format!(
+                "the `{}` call needs a user approval, and the turn has no time left to ask",
+                function_name
+            ))
+            .into());
+        }
+
+        let id = nanoid::nanoid!(8);
+        let requester = self.requester.lock().unwrap().clone();
+        let receiver = self.approvals.register(
+            id.clone(),
+            self.session.clone(),
+            function_name.clone(),
+            requester,
+        );
+
+        self.transport
+            .send_response(
+                self.session.clone(),
+                VizierResponse {
+                    timestamp: chrono::Utc::now(),
+                    content: VizierResponseContent::ApprovalRequest {
+                        id: id.clone(),
+                        name: function_name.clone(),
+                        args: serde_json::from_str::<Value>(&args)?,
+                    },
+                    attachments: vec![],
+                },
+            )
+            .await?;
+
+        let denied = VizierToolDenied(format!(
+            "the user denied the `{}` call, don't retry it unless asked to",
+            function_name
+        ));
+        match tokio::time::timeout(wait, receiver).await {
+            Ok(Ok(Decision { approved, by })) => {
+                // channels update the request once the answer is accepted
+                self.transport
+                    .send_response(
+                        self.session.clone(),
+                        VizierResponse {
+                            timestamp: chrono::Utc::now(),
+                            content: VizierResponseContent::ApprovalResolved { id, approved, by },
+                            attachments: vec![],
+                        },
+                    )
+                    .await?;
+
+                if approved {
+                    Ok((function_name, args))
+                } else {
+                    Err(denied.into())
+                }
+            }
+            Ok(Err(_)) => Err(denied.into()),
+            Err(_) => {
+                self.approvals.cancel(&id);
+                Err(VizierToolDenied(format!(
+                    "the `{}` call was not approved in time, it did not run",
+                    function_name
+                ))
+                .into())
+            }
+        }
+    }
+}
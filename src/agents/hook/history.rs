@@ -5,22 +5,26 @@ use anyhow::Result;
 use crate::{
     agents::hook::VizierSessionHook,
     schema::{
-        SessionHistoryContent, VizierRequest, VizierResponse, VizierResponseContent, VizierSession,
+        SessionHistoryContent, VizierAttachment, VizierAttachmentContent, VizierRequest,
+        VizierResponse, VizierResponseContent, VizierSession,
     },
     storage::{VizierStorage, history::HistoryStorage},
+    utils::{credential::CredentialVault, get_mime_type},
 };
 
 #[derive(Clone)]
 pub struct HistoryHook {
     storage: Arc<VizierStorage>,
     session: VizierSession,
+    vault: CredentialVault,
 }
 
 impl HistoryHook {
-    pub fn new(db: Arc<VizierStorage>, session: VizierSession) -> Self {
+    pub fn new(db: Arc<VizierStorage>, session: VizierSession, vault: CredentialVault) -> Self {
         Self {
             storage: db,
             session,
+            vault,
         }
     }
 }
@@ -31,7 +35,7 @@ impl VizierSessionHook for HistoryHook {
         self.storage
             .save_session_history(
                 self.session.clone(),
-                SessionHistoryContent::Request(req.clone()),
+                SessionHistoryContent::Request(self.vault.redact_value(req.clone())),
             )
             .await?;
 
@@ -45,12 +49,15 @@ impl VizierSessionHook for HistoryHook {
             attachments,
         } = res.clone()
         {
+            let (content, attachments) = stored_attachments(content, attachments);
             self.storage
                 .save_session_history(
                     self.session.clone(),
                     SessionHistoryContent::Response(VizierResponse {
                         timestamp: res.timestamp,
-                        content: VizierResponseContent::Message { content, stats },
+                        content: self
+                            .vault
+                            .redact_value(VizierResponseContent::Message { content, stats }),
                         attachments,
                     }),
                 )
@@ -60,3 +67,28 @@ impl VizierSessionHook for HistoryHook {
         Ok(res)
     }
 }
+
+/// files sent with the answer are kept as a note with their name, type and
+/// size, only links are stored as they are
+fn stored_attachments(
+    content: String,
+    attachments: Vec<VizierAttachment>,
+) -> (String, Vec<VizierAttachment>) {
+    let mut content = content;
+    let mut stored = vec![];
+    for attachment in attachments {
+        if let VizierAttachmentContent::Url(_) = attachment.content {
+            stored.push(attachment);
+            continue;
+        }
+
+        content.push_str(&format!(
+            "\n\n[attached {} ({}, {} bytes)]",
+            attachment.filename,
+            get_mime_type(&attachment.filename),
+            attachment.size().unwrap_or_default()
+        ));
+    }
+
+    (content, stored)
+}
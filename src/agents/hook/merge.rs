@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::{
+    agents::{hook::VizierSessionHook, process::SessionInbox},
+    schema::VizierRequest,
+};
+
+/// hands requests that arrived mid turn over to the running turn
+#[derive(Clone)]
+pub struct MergeHook {
+    inbox: Arc<SessionInbox>,
+}
+
+impl MergeHook {
+    pub fn new(inbox: Arc<SessionInbox>) -> Self {
+        Self { inbox }
+    }
+}
+
+#[async_trait::async_trait]
+impl VizierSessionHook for MergeHook {
+    async fn on_turn_boundary(&self) -> Result<Vec<VizierRequest>> {
+        Ok(self.inbox.drain())
+    }
+}
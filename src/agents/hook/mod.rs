@@ -3,8 +3,11 @@ use std::sync::Arc;
 use crate::schema::{VizierRequest, VizierResponse};
 use anyhow::Result;
 
+pub mod approval;
 pub mod debug;
 pub mod history;
+pub mod merge;
+pub mod stream;
 pub mod thinking;
 pub mod tool_calls;
 
@@ -28,10 +31,31 @@ where
     async fn on_tool_response(&self, res: VizierResponse) -> Result<VizierResponse> {
         Ok(res)
     }
+
+    async fn on_message_delta(&self, _delta: String) -> Result<()> {
+        Ok(())
+    }
+
+    /// called with the reasoning of every completion that has some
+    async fn on_reasoning(&self, _reasoning: String) -> Result<()> {
+        Ok(())
+    }
+
+    /// called once the tool calls of a turn are done, returned requests are
+    /// added to the tool results before the model continues
+    async fn on_turn_boundary(&self) -> Result<Vec<VizierRequest>> {
+        Ok(vec![])
+    }
 }
 
 pub struct VizierSessionHooks(Vec<Arc<Box<dyn VizierSessionHook>>>);
 
+impl Default for VizierSessionHooks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VizierSessionHooks {
     pub fn new() -> Self {
         Self(vec![])
@@ -85,4 +109,29 @@ impl VizierSessionHook for VizierSessionHooks {
 
         Ok(res)
     }
+
+    async fn on_message_delta(&self, delta: String) -> Result<()> {
+        for hook in self.0.iter() {
+            hook.on_message_delta(delta.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn on_reasoning(&self, reasoning: String) -> Result<()> {
+        for hook in self.0.iter() {
+            hook.on_reasoning(reasoning.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn on_turn_boundary(&self) -> Result<Vec<VizierRequest>> {
+        let mut res = vec![];
+        for hook in self.0.iter() {
+            res.extend(hook.on_turn_boundary().await?);
+        }
+
+        Ok(res)
+    }
 }
@@ -0,0 +1,37 @@
+use anyhow::Result;
+
+use crate::{
+    agents::hook::VizierSessionHook,
+    schema::{VizierResponse, VizierResponseContent, VizierSession},
+    transport::VizierTransport,
+};
+
+#[derive(Debug, Clone)]
+pub struct StreamHook {
+    transport: VizierTransport,
+    session: VizierSession,
+}
+
+impl StreamHook {
+    pub fn new(transport: VizierTransport, session: VizierSession) -> Self {
+        Self { transport, session }
+    }
+}
+
+#[async_trait::async_trait]
+impl VizierSessionHook for StreamHook {
+    async fn on_message_delta(&self, delta: String) -> Result<()> {
+        self.transport
+            .send_response(
+                self.session.clone(),
+                VizierResponse {
+                    timestamp: chrono::Utc::now(),
+                    content: VizierResponseContent::MessageDelta(delta),
+                    attachments: vec![],
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+}
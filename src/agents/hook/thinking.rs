@@ -41,4 +41,17 @@ impl VizierSessionHook for ThinkingHook {
 
         Ok((function_name, args))
     }
+
+    async fn on_reasoning(&self, reasoning: String) -> Result<()> {
+        self.transport
+            .send_response(
+                self.session.clone(),
+                VizierResponse {
+                    timestamp: chrono::Utc::now(),
+                    content: VizierResponseContent::Thinking(reasoning),
+                    attachments: vec![],
+                },
+            )
+            .await
+    }
 }
@@ -7,6 +7,8 @@ use crate::dependencies::VizierDependencies;
 use crate::schema::AgentId;
 
 pub mod agent;
+pub mod budget;
+pub mod command;
 pub mod hook;
 pub mod process;
 pub mod skill;
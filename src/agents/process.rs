@@ -1,29 +1,41 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
 
 use anyhow::Result;
 use chrono::Utc;
-use rig::message::Message;
 use tokio::task::{JoinHandle, JoinSet};
 
 use crate::{
     agents::{
-        agent::{VizierAgent, read_md_file},
+        agent::{VizierAgent, read_md_file, sender_id},
+        budget::exceeded_budget,
+        command::{CommandContext, VizierCommands},
         hook::{
-            VizierSessionHooks, debug::DebugHook, history::HistoryHook, thinking::ThinkingHook,
+            VizierSessionHooks,
+            approval::{ApprovalHook, PendingApprovals},
+            debug::DebugHook,
+            history::HistoryHook,
+            merge::MergeHook,
+            stream::StreamHook,
+            thinking::ThinkingHook,
             tool_calls::ToolCallsHook,
         },
     },
-    config::agent::AgentConfig,
+    config::agent::{AgentConfig, ConcurrencyPolicy},
     dependencies::VizierDependencies,
     error::VizierError,
     schema::{
-        AgentId, VizierChannelId, VizierRequest, VizierRequestContent, VizierResponse,
-        VizierResponseContent, VizierSession, VizierSessionDetail,
+        AgentId, SessionHistoryContent, VizierChannelId, VizierRequest, VizierRequestContent,
+        VizierResponse, VizierResponseContent, VizierSession, VizierSessionDetail,
     },
     storage::{
         VizierStorage, history::HistoryStorage, memory::MemoryStorage, session::SessionStorage,
+        state::StateStorage,
     },
     transport::VizierTransport,
+    utils::credential::CredentialVault,
 };
 
 pub async fn agent_process(agent_id: AgentId, deps: VizierDependencies) -> Result<()> {
@@ -146,15 +158,80 @@ pub async fn agent_process(agent_id: AgentId, deps: VizierDependencies) -> Resul
         }
     });
 
+    let commands = VizierCommands::new();
+    let approvals = Arc::new(PendingApprovals::default());
+
+    let downgraded = match agent_config
+        .budget
+        .as_ref()
+        .and_then(|budget| budget.downgrade.clone())
+    {
+        Some(model) => Some(Arc::new(
+            agent.with_model(agent_id.clone(), model, &deps).await?,
+        )),
+        None => None,
+    };
+    let mut inboxes = HashMap::<VizierSession, Arc<SessionInbox>>::new();
+
     while let Ok((session, request)) = recv.recv().await {
         if session.0 != agent_id {
             continue;
         }
 
+        // commands don't interrupt the running turn nor end up in the history
+        if let VizierRequestContent::Command(command) = &request.content {
+            let ctx = CommandContext {
+                session: session.clone(),
+                agent_config: agent_config.clone(),
+                storage: deps.storage.clone(),
+                approvals: approvals.clone(),
+                tier: agent.sender_tier(&session.1, &request),
+                sender: sender_id(&request),
+                user: request.user.clone(),
+            };
+            handle_command(&commands, ctx, command.clone(), deps.transport.clone()).await;
+
+            // session settings may have changed, rebuild the hooks on the next request
+            agent_sessions.remove(&session);
+            continue;
+        }
+
+        // over budget, the cheaper model answers or the request is refused
+        let mut turn_agent = agent.clone();
+        if let Some(budget) = &agent_config.budget {
+            match exceeded_budget(&deps.storage, &agent_id, budget).await {
+                Ok(None) => {}
+                Ok(Some(reason)) => match &downgraded {
+                    Some(downgraded) => turn_agent = downgraded.clone(),
+                    None => {
+                        log::warn!("{}: {}", agent_id, reason);
+                        if let VizierRequestContent::Chat(_) = request.content {
+                            let _ = deps
+                                .transport
+                                .send_response(
+                                    session.clone(),
+                                    VizierResponse {
+                                        timestamp: Utc::now(),
+                                        content: VizierResponseContent::Message {
+                                            content: format!("ERR: {}", reason),
+                                            stats: None,
+                                        },
+                                        attachments: vec![],
+                                    },
+                                )
+                                .await;
+                        }
+                        continue;
+                    }
+                },
+                Err(err) => log::error!("budget check: {}", err),
+            }
+        }
+
         // handle session_detail creator
         let session_detail_storage = deps.storage.clone();
         let session_detail_session = session.clone();
-        let session_detail_agent = agent.clone();
+        let session_detail_agent = turn_agent.clone();
         let session_detail_request = request.clone();
         detail_tasks.spawn(async move {
             let agent_id = session_detail_session.0;
@@ -173,10 +250,8 @@ pub async fn agent_process(agent_id: AgentId, deps: VizierDependencies) -> Resul
 **only response the summarize title**"#,
                     session_detail_request.to_prompt().unwrap()
                 );
-                let res = session_detail_agent
-                    .prompt(Message::system(prompt), vec![], 0, None, false)
-                    .await;
-                if let Ok((title, _)) = res {
+                let res = session_detail_agent.complete(prompt).await;
+                if let Ok((title, stats)) = res {
                     let mut title = title.clone();
                     title.truncate(60);
 
@@ -188,6 +263,22 @@ pub async fn agent_process(agent_id: AgentId, deps: VizierDependencies) -> Resul
                         title.pop();
                     }
 
+                    // the title isn't part of any turn, its usage is kept on the
+                    // system session so budgets and usage still count it
+                    let _ = session_detail_storage
+                        .save_session_history(
+                            VizierSession(agent_id.clone(), VizierChannelId::System, None),
+                            SessionHistoryContent::Response(VizierResponse {
+                                timestamp: Utc::now(),
+                                content: VizierResponseContent::Message {
+                                    content: format!("session title: {}", title),
+                                    stats: Some(stats),
+                                },
+                                attachments: vec![],
+                            }),
+                        )
+                        .await;
+
                     let detail = VizierSessionDetail {
                         agent_id,
                         channel,
@@ -199,28 +290,56 @@ pub async fn agent_process(agent_id: AgentId, deps: VizierDependencies) -> Resul
             }
         });
 
-        if let Some(handle) = main_handles.get(&session) {
-            if !handle.is_finished() {
-                let _ = deps
-                    .transport
-                    .send_response(
-                        session.clone(),
-                        crate::schema::VizierResponse {
-                            timestamp: chrono::Utc::now(),
-                            content: crate::schema::VizierResponseContent::Abort,
-                            attachments: vec![],
-                        },
-                    )
-                    .await;
+        let inbox = inboxes.entry(session.clone()).or_default().clone();
+        let request = match agent_config.concurrency_policy {
+            ConcurrencyPolicy::Interrupt => {
+                if let Some(handle) = main_handles.get(&session) {
+                    if !handle.is_finished() {
+                        let _ = deps
+                            .transport
+                            .send_response(
+                                session.clone(),
+                                crate::schema::VizierResponse {
+                                    timestamp: chrono::Utc::now(),
+                                    content: crate::schema::VizierResponseContent::Abort,
+                                    attachments: vec![],
+                                },
+                            )
+                            .await;
+                    }
+                    handle.abort();
+                }
+
+                request
             }
-            handle.abort();
-        }
+            // the running task picks it up, either at its next tool boundary
+            // (merge) or once it's done (queue)
+            ConcurrencyPolicy::Queue | ConcurrencyPolicy::Merge => {
+                // a task that died without draining its inbox can't hold the session
+                if let Some(handle) = main_handles.get(&session) {
+                    if handle.is_finished() {
+                        inbox.reset();
+                    }
+                }
+
+                match inbox.start(request) {
+                    Some(request) => request,
+                    None => continue,
+                }
+            }
+        };
 
         let agent_session = if let Some(agent_session) = agent_sessions.get(&session) {
             agent_session.clone()
         } else {
-            let agent_session =
-                AgentSession::new(agent_config.clone(), session.clone(), deps.clone())?;
+            let agent_session = AgentSession::new(
+                agent_config.clone(),
+                session.clone(),
+                deps.clone(),
+                inbox.clone(),
+                approvals.clone(),
+            )
+            .await?;
             agent_sessions.insert(session.clone(), agent_session.clone());
 
             agent_session
@@ -235,53 +354,62 @@ pub async fn agent_process(agent_id: AgentId, deps: VizierDependencies) -> Resul
         let thinking_session = session.clone();
         let thinking_handle = Arc::new(tokio::spawn(async move {
             if let VizierRequestContent::Chat(_) = thinking_request.content {
-                let _ = thinking_transport
-                    .send_response(
-                        thinking_session.clone(),
-                        crate::schema::VizierResponse {
-                            timestamp: chrono::Utc::now(),
-                            content: crate::schema::VizierResponseContent::ThinkingStart,
-                            attachments: vec![],
-                        },
-                    )
-                    .await;
+                send_thinking_start(&thinking_transport, thinking_session).await;
             }
         }));
         thinking_handles.insert(session.clone(), thinking_handle.clone());
 
-        let agent = agent.clone();
+        let agent = turn_agent;
         let agent_config = agent_config.clone();
         let session = session.clone();
         let transport = deps.transport.clone();
         let storage = deps.storage.clone();
+        let policy = agent_config.concurrency_policy;
         main_handles.insert(
             session.clone(),
             tokio::spawn(async move {
-                if let Err(err) = handle_request(
-                    agent.clone(),
-                    agent_config.clone(),
-                    session.clone(),
-                    request.clone(),
-                    transport.clone(),
-                    storage.clone(),
-                    agent_session.hooks.clone(),
-                )
-                .await
-                {
-                    log::error!("{}", err);
-                    let _ = transport
-                        .send_response(
-                            session.clone(),
-                            crate::schema::VizierResponse {
-                                timestamp: chrono::Utc::now(),
-                                content: crate::schema::VizierResponseContent::Message {
-                                    content: format!("ERR: {}", err),
-                                    stats: None,
+                let mut request = request;
+                loop {
+                    if let Err(err) = handle_request(
+                        agent.clone(),
+                        agent_config.clone(),
+                        session.clone(),
+                        request.clone(),
+                        transport.clone(),
+                        storage.clone(),
+                        agent_session.hooks.clone(),
+                    )
+                    .await
+                    {
+                        log::error!("{}", err);
+                        let _ = transport
+                            .send_response(
+                                session.clone(),
+                                crate::schema::VizierResponse {
+                                    timestamp: chrono::Utc::now(),
+                                    content: crate::schema::VizierResponseContent::Message {
+                                        content: format!("ERR: {}", err),
+                                        stats: None,
+                                    },
+                                    attachments: vec![],
                                 },
-                                attachments: vec![],
-                            },
-                        )
-                        .await;
+                            )
+                            .await;
+                    }
+
+                    if policy == ConcurrencyPolicy::Interrupt {
+                        break;
+                    }
+
+                    match inbox.next() {
+                        Some(next) => {
+                            if let VizierRequestContent::Chat(_) = next.content {
+                                send_thinking_start(&transport, session.clone()).await;
+                            }
+                            request = next;
+                        }
+                        None => break,
+                    }
                 }
 
                 thinking_handle.abort();
@@ -317,7 +445,9 @@ pub async fn handle_request(
             let memory = storage
                 .query_memory(session.0.clone(), prompt.clone(), 10, 0.5)
                 .await?;
-            let res = agent.chat(request, history, memory, Some(hooks)).await?;
+            let res = agent
+                .chat(session.clone(), request, history, memory, Some(hooks))
+                .await?;
             transport.send_response(session, res).await?;
         }
         VizierRequestContent::SilentRead(_) => {
@@ -328,7 +458,9 @@ pub async fn handle_request(
                     Some(agent_config.session_memory.max_capacity),
                 )
                 .await?;
-            let res = agent.chat(request, history, vec![], Some(hooks)).await?;
+            let res = agent
+                .chat(session.clone(), request, history, vec![], Some(hooks))
+                .await?;
             transport.send_response(session, res).await?;
         }
         VizierRequestContent::Prompt(_) | VizierRequestContent::Task(_) => {
@@ -358,31 +490,146 @@ pub async fn handle_request(
                 _ => vec![],
             };
 
-            let res = agent.chat(request, history, vec![], Some(hooks)).await?;
+            let res = agent
+                .chat(session.clone(), request, history, vec![], Some(hooks))
+                .await?;
             transport.send_response(session, res).await?;
         }
-        VizierRequestContent::Command(_) => unimplemented!(),
+        // answered by agent_process before a turn is started
+        VizierRequestContent::Command(_) => {}
     }
 
     Ok(())
 }
 
+async fn send_thinking_start(transport: &VizierTransport, session: VizierSession) {
+    let _ = transport
+        .send_response(
+            session,
+            crate::schema::VizierResponse {
+                timestamp: chrono::Utc::now(),
+                content: crate::schema::VizierResponseContent::ThinkingStart,
+                attachments: vec![],
+            },
+        )
+        .await;
+}
+
+/// requests waiting for a busy session, used by the queue and merge policies
+#[derive(Default)]
+pub struct SessionInbox(Mutex<SessionInboxState>);
+
+#[derive(Default)]
+struct SessionInboxState {
+    running: bool,
+    pending: VecDeque<VizierRequest>,
+}
+
+impl SessionInbox {
+    /// returns the request back when the session is idle and should start
+    /// working on it, otherwise it's kept for the running task
+    pub fn start(&self, request: VizierRequest) -> Option<VizierRequest> {
+        let mut state = self.0.lock().unwrap();
+        if state.running {
+            state.pending.push_back(request);
+            None
+        } else {
+            state.running = true;
+            Some(request)
+        }
+    }
+
+    /// next pending request, marks the session idle when there is none
+    pub fn next(&self) -> Option<VizierRequest> {
+        let mut state = self.0.lock().unwrap();
+        let next = state.pending.pop_front();
+        if next.is_none() {
+            state.running = false;
+        }
+
+        next
+    }
+
+    pub fn reset(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.running = false;
+        state.pending.clear();
+    }
+
+    /// takes every pending request, to be merged into the running turn
+    pub fn drain(&self) -> Vec<VizierRequest> {
+        self.0.lock().unwrap().pending.drain(..).collect()
+    }
+}
+
+pub async fn handle_command(
+    commands: &VizierCommands,
+    ctx: CommandContext,
+    command: String,
+    transport: VizierTransport,
+) {
+    let content = match commands.execute(&ctx, &command).await {
+        Ok(output) => output,
+        Err(err) => format!("ERR: {}", err),
+    };
+
+    if let Err(err) = transport
+        .send_response(
+            ctx.session,
+            VizierResponse {
+                timestamp: Utc::now(),
+                content: VizierResponseContent::Message {
+                    content,
+                    stats: None,
+                },
+                attachments: vec![],
+            },
+        )
+        .await
+    {
+        log::error!("{}", err);
+    }
+}
+
 #[derive(Clone)]
 pub struct AgentSession {
     hooks: Arc<VizierSessionHooks>,
 }
 
 impl AgentSession {
-    pub fn new(
+    pub async fn new(
         agent_config: AgentConfig,
         session: VizierSession,
         deps: VizierDependencies,
+        inbox: Arc<SessionInbox>,
+        approvals: Arc<PendingApprovals>,
     ) -> Result<Self> {
+        let settings = deps
+            .storage
+            .get_session_settings(session.clone())
+            .await
+            .unwrap_or_default();
+
         let mut hooks = VizierSessionHooks::new()
             .hook(DebugHook(session.clone()))
-            .hook(HistoryHook::new(deps.storage.clone(), session.clone()));
+            .hook(HistoryHook::new(
+                deps.storage.clone(),
+                session.clone(),
+                CredentialVault::new(deps.config.tools.credentials.clone()),
+            ));
+
+        // ask before anything else sees the call
+        if !agent_config.tools.require_approval.is_empty() {
+            hooks = hooks.hook(ApprovalHook::new(
+                deps.transport.clone(),
+                session.clone(),
+                approvals,
+                agent_config.tools.require_approval.clone(),
+                agent_config.tools.approval_timeout.map(|timeout| *timeout),
+            ));
+        }
 
-        if let Some(true) = agent_config.show_thinking {
+        if let Some(true) = settings.show_thinking.or(agent_config.show_thinking) {
             hooks = hooks.hook(ThinkingHook::new(deps.transport.clone(), session.clone()));
         }
 
@@ -390,6 +637,14 @@ impl AgentSession {
             hooks = hooks.hook(ToolCallsHook::new(deps.transport.clone(), session.clone()));
         }
 
+        if let Some(true) = agent_config.stream_response {
+            hooks = hooks.hook(StreamHook::new(deps.transport.clone(), session.clone()));
+        }
+
+        if agent_config.concurrency_policy == ConcurrencyPolicy::Merge {
+            hooks = hooks.hook(MergeHook::new(inbox));
+        }
+
         let hooks = Arc::new(hooks);
 
         Ok(Self { hooks })
@@ -0,0 +1,238 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use chrono::Utc;
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agents::tools::VizierTool,
+    config::agent::ArtifactConfig,
+    error::VizierError,
+    schema::{AgentId, ToolArtifact, VizierResponse, VizierResponseContent},
+    storage::{VizierStorage, state::StateStorage},
+};
+
+pub const READ_ARTIFACT: &str = "read_artifact";
+const DEFAULT_MAX_CHARS: usize = 8_000;
+const MAX_MATCHES: usize = 50;
+const MAX_MATCH_LINE_CHARS: usize = 300;
+
+/// stores the tool results above the threshold, the model gets a preview and
+/// the artifact id instead
+pub struct ToolArtifacts {
+    agent_id: AgentId,
+    storage: Arc<VizierStorage>,
+    config: ArtifactConfig,
+}
+
+impl ToolArtifacts {
+    pub fn new(agent_id: AgentId, storage: Arc<VizierStorage>, config: ArtifactConfig) -> Self {
+        Self {
+            agent_id,
+            storage,
+            config,
+        }
+    }
+
+    /// a result that can't be stored stays in the conversation as it is
+    pub async fn offload(&self, tool: &str, res: VizierResponse) -> VizierResponse {
+        let VizierResponseContent::ToolResponse { response } = &res.content else {
+            return res;
+        };
+        if tool == READ_ARTIFACT {
+            return res;
+        }
+
+        // strings are kept as they are so their lines can be searched
+        let content = match response {
+            serde_json::Value::String(content) => content.clone(),
+            response => serde_json::to_string_pretty(response).unwrap_or_default(),
+        };
+        let total_chars = content.chars().count();
+        if total_chars <= self.config.threshold {
+            return res;
+        }
+
+        let artifact = ToolArtifact {
+            id: nanoid::nanoid!(10),
+            agent_id: self.agent_id.clone(),
+            tool: tool.to_string(),
+            content,
+            timestamp: Utc::now(),
+        };
+        let preview = artifact
+            .content
+            .chars()
+            .take(self.config.preview_chars)
+            .collect::<String>();
+        let id = artifact.id.clone();
+        if let Err(err) = self.storage.save_artifact(artifact).await {
+            log::error!(
+                "{}: failed to store the {} result: {}",
+                self.agent_id,
+                tool,
+                err
+            );
+            return res;
+        }
+
+        let expired_before = Utc::now() - *self.config.ttl;
+        if let Err(err) = self
+            .storage
+            .delete_artifacts_before(self.agent_id.clone(), expired_before)
+            .await
+        {
+            log::warn!(
+                "{}: failed to delete expired artifacts: {}",
+                self.agent_id,
+                err
+            );
+        }
+
+        VizierResponse {
+            timestamp: res.timestamp,
+            content: VizierResponseContent::ToolResponse {
+                response: serde_json::json!({
+                    "artifact": id,
+                    "total_chars": total_chars,
+                    "preview": preview,
+                    "note": format!(
+                        "the result is too long and was stored as an artifact, use `{}` to read the rest or search it",
+                        READ_ARTIFACT
+                    ),
+                }),
+            },
+            attachments: res.attachments,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ReadArtifactArgs {
+    #[schemars(description = "id of the artifact")]
+    pub artifact: String,
+    #[schemars(description = "character to start reading from, defaults to 0")]
+    pub offset: Option<usize>,
+    #[schemars(description = "characters to read, defaults to 8000")]
+    pub max_chars: Option<usize>,
+    #[schemars(
+        description = "regular expression to search the artifact for instead of reading it, returns the matching lines with their offsets"
+    )]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ArtifactMatch {
+    pub line: usize,
+    pub offset: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ReadArtifactOutput {
+    pub artifact: String,
+    pub tool: String,
+    pub total_chars: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[schemars(description = "offset of the rest of the artifact, none once it's all read")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Vec<ArtifactMatch>>,
+}
+
+pub struct ReadArtifact {
+    pub agent_id: AgentId,
+    pub storage: Arc<VizierStorage>,
+    pub ttl: Duration,
+}
+
+fn search(content: &str, pattern: &str) -> Result<Vec<ArtifactMatch>, VizierError> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|err| VizierError(err.to_string()))?;
+
+    let mut offset = 0;
+    let mut matches = vec![];
+    for (index, line) in content.split('\n').enumerate() {
+        if regex.is_match(line) {
+            matches.push(ArtifactMatch {
+                line: index + 1,
+                offset,
+                text: line.chars().take(MAX_MATCH_LINE_CHARS).collect(),
+            });
+            if matches.len() == MAX_MATCHES {
+                break;
+            }
+        }
+        offset += line.chars().count() + 1;
+    }
+
+    Ok(matches)
+}
+
+#[async_trait::async_trait]
+impl VizierTool for ReadArtifact {
+    type Input = ReadArtifactArgs;
+    type Output = ReadArtifactOutput;
+
+    fn name() -> String {
+        READ_ARTIFACT.to_string()
+    }
+
+    fn description(&self) -> String {
+        "read a stored tool result by character range, or search it for a regular expression".into()
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        // ids are used in storage keys
+        if !args
+            .artifact
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(VizierError(format!("invalid artifact {}", args.artifact)));
+        }
+
+        let artifact = self
+            .storage
+            .get_artifact(self.agent_id.clone(), args.artifact.clone())
+            .await
+            .map_err(|err| VizierError(err.to_string()))?
+            .filter(|artifact| artifact.timestamp + self.ttl > Utc::now())
+            .ok_or(VizierError(format!("artifact {} not found", args.artifact)))?;
+        let total_chars = artifact.content.chars().count();
+
+        let mut output = ReadArtifactOutput {
+            artifact: artifact.id,
+            tool: artifact.tool,
+            total_chars,
+            content: None,
+            next_offset: None,
+            matches: None,
+        };
+
+        if let Some(pattern) = &args.pattern {
+            output.matches = Some(search(&artifact.content, pattern)?);
+            return Ok(output);
+        }
+
+        let offset = args.offset.unwrap_or(0).min(total_chars);
+        let max_chars = args.max_chars.unwrap_or(DEFAULT_MAX_CHARS).max(1);
+        output.content = Some(
+            artifact
+                .content
+                .chars()
+                .skip(offset)
+                .take(max_chars)
+                .collect(),
+        );
+        let next_offset = offset.saturating_add(max_chars);
+        output.next_offset = (next_offset < total_chars).then_some(next_offset);
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,124 @@
+use std::{path::PathBuf, sync::Arc};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agents::tools::VizierTool,
+    error::VizierError,
+    schema::{VizierAttachment, VizierAttachmentContent, VizierResponse, VizierResponseContent},
+    shell::{ShellProvider, VizierShell},
+};
+
+// the largest file any channel accepts, channels apply their own limits on top
+const MAX_ATTACHMENT_SIZE: usize = 50 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct AttachFileArgs {
+    #[schemars(
+        description = "path of the file, relative to the agent workspace (or to the shell working directory with `from_shell`)"
+    )]
+    pub path: String,
+    #[schemars(
+        description = "read the file from the shell environment (e.g. the docker container) instead of the agent workspace"
+    )]
+    #[serde(default)]
+    pub from_shell: bool,
+    #[schemars(description = "file name shown to the user, defaults to the name of the file")]
+    pub filename: Option<String>,
+}
+
+pub struct AttachFile {
+    workspace: String,
+    shell: Option<Arc<VizierShell>>,
+}
+
+impl AttachFile {
+    pub fn new(workspace: String, shell: Option<Arc<VizierShell>>) -> Self {
+        Self { workspace, shell }
+    }
+
+    async fn read_workspace_file(&self, path: &str) -> Result<Vec<u8>, VizierError> {
+        let workspace = PathBuf::from(&self.workspace)
+            .canonicalize()
+            .map_err(|err| VizierError(err.to_string()))?;
+        let path = workspace
+            .join(path)
+            .canonicalize()
+            .map_err(|_| VizierError(format!("{} not found in the workspace", path)))?;
+
+        if !path.starts_with(&workspace) {
+            return Err(VizierError(
+                "only files of the workspace can be attached".into(),
+            ));
+        }
+
+        tokio::fs::read(path)
+            .await
+            .map_err(|err| VizierError(err.to_string()))
+    }
+}
+
+#[async_trait::async_trait]
+impl VizierTool for AttachFile {
+    type Input = AttachFileArgs;
+    type Output = VizierResponse;
+
+    fn name() -> String {
+        "attach_file".to_string()
+    }
+
+    fn delivers_attachments() -> bool {
+        true
+    }
+
+    fn description(&self) -> String {
+        "attach a file (e.g. a generated CSV, chart or report) to your answer, the user receives it once you reply".into()
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        let bytes = if args.from_shell {
+            let shell = self
+                .shell
+                .as_ref()
+                .ok_or(VizierError("shell access is not enabled".into()))?;
+
+            shell
+                .read_file(args.path.clone())
+                .await
+                .map_err(|err| VizierError(err.to_string()))?
+        } else {
+            self.read_workspace_file(&args.path).await?
+        };
+
+        if bytes.len() > MAX_ATTACHMENT_SIZE {
+            return Err(VizierError(format!(
+                "{} is too large to attach ({} MB, at most {} MB)",
+                args.path,
+                bytes.len() / 1024 / 1024,
+                MAX_ATTACHMENT_SIZE / 1024 / 1024
+            )));
+        }
+
+        let filename = args.filename.unwrap_or_else(|| {
+            PathBuf::from(&args.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(args.path.clone())
+        });
+
+        Ok(VizierResponse {
+            timestamp: Utc::now(),
+            content: VizierResponseContent::ToolResponse {
+                response: serde_json::Value::String(format!(
+                    "{} will be sent along with your answer",
+                    filename
+                )),
+            },
+            attachments: vec![VizierAttachment {
+                filename,
+                content: VizierAttachmentContent::Bytes(bytes),
+            }],
+        })
+    }
+}
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use reqwest::Method;
+use rig::completion::ToolDefinition;
+use serde_json::Value;
+
+use crate::{
+    agents::tools::{VizierToolDyn, VizierToolSet, ptc::run_script},
+    config::{
+        agent::PythonLimitsConfig,
+        tools::custom::{CustomToolConfig, CustomToolExecutor},
+    },
+    error::{VizierError, throw_vizier_error},
+    shell::{ShellProvider, VizierShell},
+    utils::egress::EgressPolicy,
+};
+
+/// a tool declared in the `tools` directory
+pub struct CustomTool {
+    config: CustomToolConfig,
+    shell: Arc<VizierShell>,
+    limits: PythonLimitsConfig,
+    egress: Arc<EgressPolicy>,
+}
+
+impl CustomTool {
+    pub fn new(
+        config: CustomToolConfig,
+        shell: Arc<VizierShell>,
+        limits: PythonLimitsConfig,
+        egress: Arc<EgressPolicy>,
+    ) -> Self {
+        Self {
+            config,
+            shell,
+            limits,
+            egress,
+        }
+    }
+}
+
+/// replaces the `{{arg}}` placeholders in a single pass, so a value can't
+/// bring in placeholders of its own. strings are inserted as they are and
+/// anything else as JSON, unknown placeholders are left alone
+fn render(template: &str, args: &Value, escape: impl Fn(&str) -> String) -> String {
+    let mut res = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        res.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        match args.get(&after[..end]) {
+            Some(Value::String(value)) => res.push_str(&escape(value)),
+            Some(value) => res.push_str(&escape(&value.to_string())),
+            None => res.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+    res.push_str(rest);
+
+    res
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn url_encode(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// escapes for a JSON string, the template provides the quotes
+fn json_escape(value: &str) -> String {
+    let quoted = Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+fn expand_env(template: &str) -> Result<String, VizierError> {
+    shellexpand::env(template)
+        .map(|expanded| expanded.to_string())
+        .map_err(|err| VizierError(err.to_string()))
+}
+
+impl CustomTool {
+    async fn execute(&self, args: Value) -> Result<Value, VizierError> {
+        let output = match &self.config.executor {
+            CustomToolExecutor::Shell { command } => self
+                .shell
+                .exec(render(command, &args, shell_quote))
+                .await
+                .map_err(|err| VizierError(err.to_string()))?,
+            CustomToolExecutor::Python { path } => {
+                let script = tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|err| VizierError(format!("{}: {}", path, err)))?;
+
+                run_script(
+                    script,
+                    Arc::new(VizierToolSet::new()),
+                    Some(args),
+                    self.limits.clone(),
+                )
+                .await?
+            }
+            CustomToolExecutor::Http {
+                method,
+                url,
+                headers,
+                body,
+            } => {
+                let method = Method::from_bytes(method.to_uppercase().as_bytes())
+                    .map_err(|e| VizierError(format!("Invalid HTTP method: {}", e)))?;
+
+                // the rendered url is checked, args can't point the tool elsewhere
+                let url = render(&expand_env(url)?, &args, url_encode);
+                let client = self.egress.client_for(&url, None)?;
+                let mut request = client.request(method, &url);
+                for (key, value) in headers {
+                    request = request.header(key, render(&expand_env(value)?, &args, |v| v.into()));
+                }
+                if let Some(body) = body {
+                    request = request.body(render(&expand_env(body)?, &args, json_escape));
+                }
+
+                match request.send().await {
+                    Ok(response) => self.egress.read_text(response).await?,
+                    Err(err) => return throw_vizier_error("custom tool: request error", err),
+                }
+            }
+        };
+
+        // structured outputs stay structured
+        Ok(serde_json::from_str(&output).unwrap_or(Value::String(output)))
+    }
+}
+
+#[async_trait::async_trait]
+impl VizierToolDyn for CustomTool {
+    fn tool_name(&self) -> String {
+        self.config.name.clone()
+    }
+
+    fn tool_def(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.config.name.clone(),
+            description: self.config.description.clone(),
+            parameters: self.config.input.clone(),
+        }
+    }
+
+    fn description(&self) -> String {
+        self.config.description.clone()
+    }
+
+    fn input_schema(&self) -> Value {
+        self.config.input.clone()
+    }
+
+    fn output_schema(&self) -> Value {
+        serde_json::json!({})
+    }
+
+    fn exclusive(&self) -> bool {
+        // commands may touch the shared shell workspace
+        matches!(self.config.executor, CustomToolExecutor::Shell { .. })
+    }
+
+    fn delivers_attachments(&self) -> bool {
+        false
+    }
+
+    async fn tool_call(&self, args: String) -> Result<String, VizierError> {
+        let args = serde_json::from_str(&args).map_err(|err| VizierError(err.to_string()))?;
+        let output = self.execute(args).await?;
+
+        serde_json::to_string(&output).map_err(|err| VizierError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn render_inserts_args() {
+        let args = json!({ "city": "Paris", "days": 3 });
+
+        let res = render("weather {{city}} --days {{days}}", &args, |value| {
+            value.to_string()
+        });
+
+        assert_eq!(res, "weather Paris --days 3");
+    }
+
+    #[test]
+    fn render_keeps_unknown_placeholders() {
+        let res = render("{{missing}} and {{unclosed", &json!({}), |value| {
+            value.to_string()
+        });
+
+        assert_eq!(res, "{{missing}} and {{unclosed");
+    }
+
+    #[test]
+    fn render_does_not_expand_values() {
+        let args = json!({ "a": "{{b}}", "b": "secret" });
+
+        assert_eq!(render("{{a}}", &args, |value| value.to_string()), "{{b}}");
+    }
+
+    #[test]
+    fn render_escapes_values() {
+        let args = json!({ "name": "it's" });
+
+        assert_eq!(
+            render("echo {{name}}", &args, shell_quote),
+            "echo 'it'\\''s'"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use scraper::{Html, Selector};
+
+use crate::error::VizierError;
+
+// never part of the readable content
+const NOISE: &str = "script, style, noscript, template, svg, canvas, iframe, form, button, \
+     nav, aside, [role=navigation], [role=banner], [role=contentinfo], [aria-hidden=true]";
+
+// page chrome, only stripped when guessing the main content
+const CHROME: &str = "body > header, body > footer, #header, #footer, .header, .footer, \
+     .sidebar, .menu, .cookie, .advertisement";
+
+const MAIN: [&str; 5] = ["main", "article", "[role=main]", "#content", "body"];
+
+fn parse_selector(selector: &str) -> Result<Selector, VizierError> {
+    Selector::parse(selector).map_err(|err| VizierError(format!("{}: {}", selector, err)))
+}
+
+fn strip(document: &mut Html, selector: &str) -> Result<(), VizierError> {
+    let selector = parse_selector(selector)?;
+    let ids = document
+        .select(&selector)
+        .map(|element| element.id())
+        .collect::<Vec<_>>();
+
+    for id in ids {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+
+    Ok(())
+}
+
+pub fn extract_title(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let selector = parse_selector("title").ok()?;
+
+    document
+        .select(&selector)
+        .next()
+        .map(|title| title.text().collect::<String>().trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// the readable content of a page as markdown, either the elements matching
+/// the selector or the main content without the navigation and scripts
+pub fn extract_content(html: &str, selector: Option<&str>) -> Result<String, VizierError> {
+    let mut document = Html::parse_document(html);
+    strip(&mut document, NOISE)?;
+
+    let html = match selector {
+        Some(selector) => {
+            let selector = parse_selector(selector)?;
+            let matches = document
+                .select(&selector)
+                .map(|element| element.html())
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                return Err(VizierError("the selector matched nothing".into()));
+            }
+
+            matches.join("\n")
+        }
+        None => {
+            strip(&mut document, CHROME)?;
+
+            MAIN.iter()
+                .filter_map(|selector| parse_selector(selector).ok())
+                .find_map(|selector| document.select(&selector).next().map(|main| main.html()))
+                .unwrap_or_else(|| document.html())
+        }
+    };
+
+    Ok(html2md::parse_html(&html).trim().to_string())
+}
+
+/// a window of the content, cut at a line break when there is one in the
+/// second half, and the offset of the rest
+pub fn page(content: &str, offset: usize, max_chars: usize) -> (String, Option<usize>) {
+    let chars = content.chars().collect::<Vec<_>>();
+    if offset >= chars.len() {
+        return (String::new(), None);
+    }
+
+    let mut end = offset.saturating_add(max_chars).min(chars.len());
+    let cut = chars[offset..end]
+        .iter()
+        .rposition(|c| *c == '\n')
+        .filter(|pos| end < chars.len() && *pos > max_chars / 2);
+    if let Some(pos) = cut {
+        end = offset + pos + 1;
+    }
+
+    (
+        chars[offset..end].iter().collect(),
+        (end < chars.len()).then_some(end),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_cuts_at_line_break() {
+        let (text, next) = page("first line\nsecond line", 0, 15);
+
+        assert_eq!(text, "first line\n");
+        assert_eq!(next, Some(11));
+    }
+
+    #[test]
+    fn page_continues_from_offset() {
+        let (text, next) = page("first line\nsecond line", 11, 15);
+
+        assert_eq!(text, "second line");
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn page_past_the_end_is_empty() {
+        assert_eq!(page("short", 10, 15), (String::new(), None));
+    }
+
+    #[test]
+    fn page_counts_chars() {
+        let (text, next) = page("héllo wörld", 0, 5);
+
+        assert_eq!(text, "héllo");
+        assert_eq!(next, Some(5));
+    }
+}
@@ -1,14 +1,48 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::{
-    agents::tools::VizierTool,
-    error::{throw_vizier_error, VizierError},
+    agents::tools::{
+        VizierTool,
+        fetch::extract::{extract_content, extract_title, page},
+    },
+    error::{VizierError, throw_vizier_error},
+    storage::{VizierStorage, state::StateStorage},
+    utils::{credential::CredentialVault, egress::EgressPolicy},
 };
 
+mod extract;
+
+const DEFAULT_MAX_CHARS: usize = 20_000;
+
+// pages read in several calls are only fetched once
+const CACHE_TTL_MINUTES: i64 = 15;
+
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
 pub struct FetchArgs {
     #[schemars(description = "URL of the webpage to fetch")]
     pub url: String,
+    #[schemars(
+        description = "name of a configured credential to authenticate with, see the tool description"
+    )]
+    pub credential: Option<String>,
+    #[schemars(
+        description = "CSS selector of the part of the page to read (e.g. `#pricing`, `table.results`), the main content otherwise"
+    )]
+    pub selector: Option<String>,
+    #[schemars(
+        description = "character to start reading from, the `next_offset` of the previous call"
+    )]
+    pub offset: Option<usize>,
+    #[schemars(description = "characters to return at most, defaults to 20000")]
+    pub max_chars: Option<usize>,
+    #[schemars(description = "fetch the page again instead of reading the cached copy")]
+    #[serde(default)]
+    pub refresh: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
@@ -17,25 +51,76 @@ pub struct FetchOutput {
     pub content: String,
     #[schemars(description = "Title of the webpage if found")]
     pub title: Option<String>,
+    #[schemars(description = "length of the whole content in characters")]
+    pub total_chars: usize,
+    #[schemars(description = "pass as `offset` to read the rest, not set once everything is read")]
+    pub next_offset: Option<usize>,
 }
 
-pub struct FetchWebpage;
+#[derive(Debug, Deserialize, Serialize)]
+struct CachedPage {
+    fetched_at: DateTime<Utc>,
+    title: Option<String>,
+    content: String,
+}
 
-#[async_trait::async_trait]
-impl VizierTool for FetchWebpage {
-    type Input = FetchArgs;
-    type Output = FetchOutput;
+pub struct FetchWebpage {
+    vault: Arc<CredentialVault>,
+    egress: Arc<EgressPolicy>,
+    storage: Arc<VizierStorage>,
+}
 
-    fn name() -> String {
-        "fetch".to_string()
+impl FetchWebpage {
+    pub fn new(
+        vault: Arc<CredentialVault>,
+        egress: Arc<EgressPolicy>,
+        storage: Arc<VizierStorage>,
+    ) -> Self {
+        Self {
+            vault,
+            egress,
+            storage,
+        }
     }
 
-    fn description(&self) -> String {
-        "Fetch a webpage and convert its HTML content to markdown. Use this when you need to read content from a URL. Returns the markdown content and page title if available.".to_string()
+    fn cache_key(args: &FetchArgs) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(args.url.as_bytes());
+        for part in [&args.selector, &args.credential] {
+            hasher.update(b"\n");
+            hasher.update(part.as_deref().unwrap_or("").as_bytes());
+        }
+
+        format!("fetch__{:x}", hasher.finalize())
     }
 
-    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
-        let response = reqwest::get(&args.url).await;
+    async fn cached(&self, key: &str) -> Option<CachedPage> {
+        let value = self.storage.get_state(key.to_string()).await.ok()??;
+        let page = serde_json::from_value::<CachedPage>(value).ok();
+
+        match page {
+            Some(page) if Utc::now() - page.fetched_at < Duration::minutes(CACHE_TTL_MINUTES) => {
+                Some(page)
+            }
+            // stale or unreadable copies are dropped, a new fetch saves it again
+            _ => {
+                if let Err(err) = self.storage.delete_state(key.to_string()).await {
+                    log::warn!("fetch: failed to delete the cached {}: {}", key, err);
+                }
+                None
+            }
+        }
+    }
+
+    async fn fetch(&self, args: &FetchArgs) -> Result<CachedPage, VizierError> {
+        let client = self
+            .egress
+            .client_for(&args.url, args.credential.as_deref())?;
+        let response = self
+            .vault
+            .request(&client, Method::GET, &args.url, args.credential.as_deref())?
+            .send()
+            .await;
 
         if let Err(err) = response {
             return throw_vizier_error("fetch: http error", err);
@@ -43,39 +128,81 @@ impl VizierTool for FetchWebpage {
         let response = response.unwrap();
 
         if !response.status().is_success() {
-            return throw_vizier_error("fetch: status error", response.error_for_status().err().unwrap());
-        }
-
-        let html = response.text().await;
-
-        if let Err(err) = html {
-            return throw_vizier_error("fetch: text error", err);
+            return throw_vizier_error(
+                "fetch: status error",
+                response.error_for_status().err().unwrap(),
+            );
         }
-        let html = html.unwrap();
 
-        let title = extract_title(&html);
-        let markdown = html2md::parse_html(&html);
+        let html = self.egress.read_text(response).await?;
+        // pages may echo the credential back
+        let html = self.vault.redact(&html);
 
-        Ok(FetchOutput {
-            content: markdown,
-            title,
+        Ok(CachedPage {
+            fetched_at: Utc::now(),
+            title: extract_title(&html),
+            content: extract_content(&html, args.selector.as_deref())?,
         })
     }
 }
 
-fn extract_title(html: &str) -> Option<String> {
-    let lower = html.to_lowercase();
-    if let Some(start) = lower.find("<title") {
-        if let Some(tag_end) = lower[start..].find('>') {
-            let content_start = start + tag_end + 1;
-            if let Some(end) = lower[content_start..].find("</title>") {
-                let title = &html[content_start..content_start + end];
-                let title = title.trim();
-                if !title.is_empty() {
-                    return Some(title.to_string());
+#[async_trait::async_trait]
+impl VizierTool for FetchWebpage {
+    type Input = FetchArgs;
+    type Output = FetchOutput;
+
+    fn name() -> String {
+        "fetch".to_string()
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Fetch a webpage and convert its main content to markdown. Use this when you need to read content from a URL. Long pages are returned in parts, call again with `next_offset` to read on. Returns the markdown content and page title if available.{}",
+            self.vault.describe()
+        )
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        // cached pages still have to be within the egress policy
+        self.egress
+            .client_for(&args.url, args.credential.as_deref())?;
+
+        let key = Self::cache_key(&args);
+        let cached = if args.refresh {
+            None
+        } else {
+            self.cached(&key).await
+        };
+
+        let fetched = match cached {
+            Some(fetched) => fetched,
+            None => {
+                let fetched = self.fetch(&args).await?;
+                match serde_json::to_value(&fetched) {
+                    Ok(value) => {
+                        if let Err(err) = self.storage.save_state(key, value).await {
+                            log::warn!("fetch: failed to cache {}: {}", args.url, err);
+                        }
+                    }
+                    Err(err) => log::warn!("fetch: failed to cache {}: {}", args.url, err),
                 }
+
+                fetched
             }
-        }
+        };
+
+        let (content, next_offset) = page(
+            &fetched.content,
+            args.offset.unwrap_or(0),
+            // an empty page would never reach the end
+            args.max_chars.unwrap_or(DEFAULT_MAX_CHARS).max(1),
+        );
+
+        Ok(FetchOutput {
+            content,
+            title: fetched.title,
+            total_chars: fetched.content.chars().count(),
+            next_offset,
+        })
     }
-    None
-}
\ No newline at end of file
+}
@@ -0,0 +1,463 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::{agents::tools::VizierTool, error::VizierError};
+
+mod patch;
+
+pub const DEFAULT_MAX_FILE_BYTES: usize = 1024 * 1024;
+const MAX_LIST_ENTRIES: usize = 500;
+const MAX_GREP_MATCHES: usize = 200;
+const MAX_GREP_LINE_CHARS: usize = 300;
+
+/// the directory the file tools are confined to
+pub struct FileRoot {
+    root: PathBuf,
+    max_file_bytes: usize,
+}
+
+impl FileRoot {
+    pub fn new(root: PathBuf, max_file_bytes: usize) -> Result<Self, VizierError> {
+        std::fs::create_dir_all(&root).map_err(|err| VizierError(err.to_string()))?;
+        let root = root
+            .canonicalize()
+            .map_err(|err| VizierError(err.to_string()))?;
+
+        Ok(Self {
+            root,
+            max_file_bytes,
+        })
+    }
+
+    /// resolves a path inside the root, `..` and symlinks can't leave it
+    fn resolve(&self, path: &str) -> Result<PathBuf, VizierError> {
+        let outside = || VizierError(format!("{} is outside of the workspace", path));
+
+        let relative = Path::new(path);
+        let relative = if relative.is_absolute() {
+            relative.strip_prefix(&self.root).map_err(|_| outside())?
+        } else {
+            relative
+        };
+
+        let mut resolved = self.root.clone();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::ParentDir => {
+                    if !resolved.pop() || !resolved.starts_with(&self.root) {
+                        return Err(outside());
+                    }
+                }
+                Component::CurDir => {}
+                Component::RootDir | Component::Prefix(_) => return Err(outside()),
+            }
+
+            // every symlink on the way has to lead to an existing path inside
+            // the root, a dangling one could be written through
+            let is_symlink = std::fs::symlink_metadata(&resolved)
+                .is_ok_and(|metadata| metadata.file_type().is_symlink());
+            if is_symlink {
+                resolved = resolved.canonicalize().map_err(|_| outside())?;
+                if !resolved.starts_with(&self.root) {
+                    return Err(outside());
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// the files and directories matching a glob, relative to the root
+    fn glob(&self, pattern: &str) -> Result<Vec<PathBuf>, VizierError> {
+        if Path::new(pattern)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(VizierError(format!(
+                "{} has to be relative to the workspace",
+                pattern
+            )));
+        }
+
+        let pattern = self.root.join(pattern).to_string_lossy().to_string();
+        let paths = glob::glob(&pattern).map_err(|err| VizierError(err.to_string()))?;
+
+        Ok(paths
+            .filter_map(|path| path.ok())
+            .filter(|path| {
+                path.canonicalize()
+                    .is_ok_and(|path| path.starts_with(&self.root))
+            })
+            .collect())
+    }
+
+    async fn read(&self, path: &Path) -> Result<String, VizierError> {
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .map_err(|_| VizierError(format!("{} not found", self.relative(path))))?;
+        if metadata.len() as usize > self.max_file_bytes {
+            return Err(VizierError(format!(
+                "{} is larger than {} bytes",
+                self.relative(path),
+                self.max_file_bytes
+            )));
+        }
+
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|err| VizierError(err.to_string()))?;
+        String::from_utf8(bytes)
+            .map_err(|_| VizierError(format!("{} is not a text file", self.relative(path))))
+    }
+
+    async fn write(&self, path: &Path, content: &str) -> Result<(), VizierError> {
+        if content.len() > self.max_file_bytes {
+            return Err(VizierError(format!(
+                "the content is larger than {} bytes",
+                self.max_file_bytes
+            )));
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| VizierError(err.to_string()))?;
+        }
+
+        tokio::fs::write(path, content)
+            .await
+            .map_err(|err| VizierError(err.to_string()))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ReadFileArgs {
+    #[schemars(description = "path of the file, relative to the workspace")]
+    pub path: String,
+    #[schemars(description = "first line to read, starts from 1")]
+    pub start_line: Option<usize>,
+    #[schemars(description = "last line to read, included")]
+    pub end_line: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ReadFileOutput {
+    pub content: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub total_lines: usize,
+}
+
+pub struct ReadFile(pub Arc<FileRoot>);
+
+#[async_trait::async_trait]
+impl VizierTool for ReadFile {
+    type Input = ReadFileArgs;
+    type Output = ReadFileOutput;
+
+    fn name() -> String {
+        "read_file".to_string()
+    }
+
+    fn description(&self) -> String {
+        "read a text file of the workspace, optionally only a range of lines".into()
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        let path = self.0.resolve(&args.path)?;
+        let content = self.0.read(&path).await?;
+
+        let lines = content.lines().collect::<Vec<_>>();
+        let start_line = args.start_line.unwrap_or(1).max(1);
+        let end_line = args.end_line.unwrap_or(lines.len()).min(lines.len());
+
+        Ok(ReadFileOutput {
+            content: lines
+                .get(start_line - 1..end_line)
+                .unwrap_or_default()
+                .join("\n"),
+            start_line,
+            end_line,
+            total_lines: lines.len(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct WriteFileArgs {
+    #[schemars(description = "path of the file, relative to the workspace")]
+    pub path: String,
+    pub content: String,
+    #[schemars(description = "add the content to the end of the file instead of replacing it")]
+    #[serde(default)]
+    pub append: bool,
+}
+
+pub struct WriteFile(pub Arc<FileRoot>);
+
+#[async_trait::async_trait]
+impl VizierTool for WriteFile {
+    type Input = WriteFileArgs;
+    type Output = String;
+
+    fn name() -> String {
+        "write_file".to_string()
+    }
+
+    fn exclusive() -> bool {
+        true
+    }
+
+    fn description(&self) -> String {
+        "write a text file of the workspace, missing directories are created".into()
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        let path = self.0.resolve(&args.path)?;
+
+        let content = if args.append && path.exists() {
+            format!("{}{}", self.0.read(&path).await?, args.content)
+        } else {
+            args.content
+        };
+        self.0.write(&path, &content).await?;
+
+        Ok(format!("{} written", self.0.relative(&path)))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ListFilesArgs {
+    #[schemars(
+        description = "glob relative to the workspace, e.g. `*` or `src/**/*.rs`, defaults to `*`"
+    )]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct FileEntry {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ListFilesOutput {
+    pub entries: Vec<FileEntry>,
+    #[schemars(description = "more files matched than listed, narrow the pattern")]
+    pub truncated: bool,
+}
+
+pub struct ListFiles(pub Arc<FileRoot>);
+
+#[async_trait::async_trait]
+impl VizierTool for ListFiles {
+    type Input = ListFilesArgs;
+    type Output = ListFilesOutput;
+
+    fn name() -> String {
+        "list_files".to_string()
+    }
+
+    fn description(&self) -> String {
+        "list the files and directories of the workspace matching a glob".into()
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        let paths = self.0.glob(args.pattern.as_deref().unwrap_or("*"))?;
+
+        let truncated = paths.len() > MAX_LIST_ENTRIES;
+        let entries = paths
+            .into_iter()
+            .take(MAX_LIST_ENTRIES)
+            .map(|path| {
+                let metadata = path.metadata().ok();
+                FileEntry {
+                    path: self.0.relative(&path),
+                    is_dir: metadata.as_ref().is_some_and(|m| m.is_dir()),
+                    size: metadata.map(|m| m.len()).unwrap_or(0),
+                }
+            })
+            .collect();
+
+        Ok(ListFilesOutput { entries, truncated })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct GrepFilesArgs {
+    #[schemars(description = "regular expression to search for")]
+    pub pattern: String,
+    #[schemars(description = "glob of the files to search, defaults to `**/*`")]
+    pub files: Option<String>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct GrepFilesOutput {
+    pub matches: Vec<GrepMatch>,
+    #[schemars(description = "more lines matched than returned, narrow the search")]
+    pub truncated: bool,
+}
+
+pub struct GrepFiles(pub Arc<FileRoot>);
+
+#[async_trait::async_trait]
+impl VizierTool for GrepFiles {
+    type Input = GrepFilesArgs;
+    type Output = GrepFilesOutput;
+
+    fn name() -> String {
+        "grep_files".to_string()
+    }
+
+    fn description(&self) -> String {
+        "search the text files of the workspace for a regular expression, returns the matching lines".into()
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        let regex = RegexBuilder::new(&args.pattern)
+            .case_insensitive(args.case_insensitive)
+            .build()
+            .map_err(|err| VizierError(err.to_string()))?;
+
+        let mut matches = vec![];
+        let mut truncated = false;
+        for path in self.0.glob(args.files.as_deref().unwrap_or("**/*"))? {
+            if !path.is_file() {
+                continue;
+            }
+            // binary and oversized files are skipped
+            let Ok(content) = self.0.read(&path).await else {
+                continue;
+            };
+
+            for (index, line) in content.lines().enumerate() {
+                if !regex.is_match(line) {
+                    continue;
+                }
+                if matches.len() == MAX_GREP_MATCHES {
+                    truncated = true;
+                    break;
+                }
+
+                matches.push(GrepMatch {
+                    path: self.0.relative(&path),
+                    line: index + 1,
+                    text: line.chars().take(MAX_GREP_LINE_CHARS).collect(),
+                });
+            }
+
+            if truncated {
+                break;
+            }
+        }
+
+        Ok(GrepFilesOutput { matches, truncated })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct PatchFileArgs {
+    #[schemars(
+        description = "unified diff (`--- a/path`, `+++ b/path`, `@@ -l,n +l,n @@` hunks), may change several files, `/dev/null` creates or deletes a file"
+    )]
+    pub patch: String,
+}
+
+pub struct PatchFile(pub Arc<FileRoot>);
+
+#[async_trait::async_trait]
+impl VizierTool for PatchFile {
+    type Input = PatchFileArgs;
+    type Output = String;
+
+    fn name() -> String {
+        "patch_file".to_string()
+    }
+
+    fn exclusive() -> bool {
+        true
+    }
+
+    fn description(&self) -> String {
+        "apply a unified diff to the files of the workspace, nothing is changed when a hunk doesn't apply".into()
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        let files = patch::parse(&args.patch)?;
+
+        // every file is patched in memory before anything is written
+        let mut changes = vec![];
+        for file in files {
+            match (&file.old_path, &file.new_path) {
+                (None, Some(new_path)) => {
+                    let path = self.0.resolve(new_path)?;
+                    if path.exists() {
+                        return Err(VizierError(format!("{} already exists", new_path)));
+                    }
+                    changes.push((path, Some(patch::apply("", &file.hunks)?)));
+                }
+                (Some(old_path), None) => {
+                    let path = self.0.resolve(old_path)?;
+                    if !path.is_file() {
+                        return Err(VizierError(format!("{} not found", old_path)));
+                    }
+                    changes.push((path, None));
+                }
+                (Some(old_path), Some(new_path)) => {
+                    let old = self.0.resolve(old_path)?;
+                    let content = self.0.read(&old).await?;
+                    let patched = patch::apply(&content, &file.hunks)
+                        .map_err(|err| VizierError(format!("{}: {}", old_path, err)))?;
+
+                    let new = self.0.resolve(new_path)?;
+                    if new != old {
+                        changes.push((old, None));
+                    }
+                    changes.push((new, Some(patched)));
+                }
+                (None, None) => return Err(VizierError("patch without a file path".into())),
+            }
+        }
+
+        let mut summary = vec![];
+        for (path, content) in changes {
+            match content {
+                Some(content) => {
+                    self.0.write(&path, &content).await?;
+                    summary.push(format!("patched {}", self.0.relative(&path)));
+                }
+                None => {
+                    tokio::fs::remove_file(&path)
+                        .await
+                        .map_err(|err| VizierError(err.to_string()))?;
+                    summary.push(format!("deleted {}", self.0.relative(&path)));
+                }
+            }
+        }
+
+        Ok(summary.join("\n"))
+    }
+}
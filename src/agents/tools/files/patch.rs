@@ -0,0 +1,254 @@
+use crate::error::VizierError;
+
+#[derive(Debug, Clone, PartialEq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    old_start: usize,
+    lines: Vec<HunkLine>,
+}
+
+/// the changes of a single file, a missing path is `/dev/null`
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+fn parse_path(line: &str) -> Option<String> {
+    // `--- a/src/main.rs\t2024-01-01 00:00:00`
+    let path = line[4..].split('\t').next().unwrap_or("").trim();
+    if path == "/dev/null" {
+        return None;
+    }
+
+    Some(
+        path.strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path)
+            .to_string(),
+    )
+}
+
+/// `@@ -12,5 +12,6 @@`, an omitted count is 1
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (start, count) = match range.split_once(',') {
+        Some((start, count)) => (start, count.parse().ok()?),
+        None => (range, 1),
+    };
+
+    Some((start.parse().ok()?, count))
+}
+
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut parts = line.trim_start_matches("@@").split_whitespace();
+    let (old_start, old_count) = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let (_, new_count) = parse_range(parts.next()?.strip_prefix('+')?)?;
+
+    Some((old_start, old_count, new_count))
+}
+
+/// parses a unified diff, the `diff --git` and `index` lines are skipped
+pub fn parse(patch: &str) -> Result<Vec<FilePatch>, VizierError> {
+    let lines = patch.lines().collect::<Vec<_>>();
+    let mut files: Vec<FilePatch> = vec![];
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ ")) {
+            files.push(FilePatch {
+                old_path: parse_path(line),
+                new_path: parse_path(lines[i + 1]),
+                hunks: vec![],
+            });
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let file = files
+                .last_mut()
+                .ok_or(VizierError("hunk without a `---`/`+++` file header".into()))?;
+            let (old_start, mut old_left, mut new_left) = parse_hunk_header(line)
+                .ok_or(VizierError(format!("invalid hunk header: {}", line)))?;
+
+            let mut hunk = Hunk {
+                old_start,
+                lines: vec![],
+            };
+            i += 1;
+            while i < lines.len() && (old_left > 0 || new_left > 0) {
+                let line = lines[i];
+                match line.chars().next() {
+                    Some('-') => {
+                        hunk.lines.push(HunkLine::Remove(line[1..].to_string()));
+                        old_left = old_left.saturating_sub(1);
+                    }
+                    Some('+') => {
+                        hunk.lines.push(HunkLine::Add(line[1..].to_string()));
+                        new_left = new_left.saturating_sub(1);
+                    }
+                    Some('\\') => {}
+                    // some editors strip the space of empty context lines
+                    Some(' ') | None => {
+                        hunk.lines
+                            .push(HunkLine::Context(line.get(1..).unwrap_or("").to_string()));
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
+                    }
+                    _ => return Err(VizierError(format!("invalid hunk line: {}", line))),
+                }
+                i += 1;
+            }
+
+            file.hunks.push(hunk);
+            continue;
+        }
+
+        i += 1;
+    }
+
+    if files.is_empty() {
+        return Err(VizierError("no file changes found in the patch".into()));
+    }
+
+    Ok(files)
+}
+
+fn find(lines: &[String], old: &[String], expected: usize, trim: bool) -> Option<usize> {
+    if old.len() > lines.len() {
+        return None;
+    }
+
+    let matches_at = |pos: usize| {
+        lines[pos..pos + old.len()]
+            .iter()
+            .zip(old)
+            .all(|(line, old)| {
+                if trim {
+                    line.trim_end() == old.trim_end()
+                } else {
+                    line == old
+                }
+            })
+    };
+
+    // closest match to where the hunk says it is
+    let last = lines.len() - old.len();
+    let expected = expected.min(last);
+    (0..=last)
+        .flat_map(|distance| {
+            [
+                expected.checked_sub(distance),
+                (distance > 0).then_some(expected + distance),
+            ]
+        })
+        .flatten()
+        .filter(|pos| *pos <= last)
+        .find(|pos| matches_at(*pos))
+}
+
+/// applies the hunks of a file, hunks may have moved a few lines since the
+/// diff was made
+pub fn apply(content: &str, hunks: &[Hunk]) -> Result<String, VizierError> {
+    let trailing_newline = content.is_empty() || content.ends_with('\n');
+    let mut lines = content
+        .lines()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+
+    let mut delta: isize = 0;
+    for (index, hunk) in hunks.iter().enumerate() {
+        let old = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(line) | HunkLine::Remove(line) => Some(line.clone()),
+                HunkLine::Add(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let new = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(line) | HunkLine::Add(line) => Some(line.clone()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        let expected = (hunk.old_start.saturating_sub(1) as isize + delta).max(0) as usize;
+        let pos = if old.is_empty() {
+            // an insertion, `-0,0` means the start of the file
+            let start = if hunk.old_start == 0 { 0 } else { expected + 1 };
+            start.min(lines.len())
+        } else {
+            find(&lines, &old, expected, false)
+                .or_else(|| find(&lines, &old, expected, true))
+                .ok_or(VizierError(format!(
+                    "hunk {} does not apply, the file doesn't have its lines",
+                    index + 1
+                )))?
+        };
+
+        delta += new.len() as isize - old.len() as isize;
+        lines.splice(pos..pos + old.len(), new);
+    }
+
+    let mut res = lines.join("\n");
+    if trailing_newline && !res.is_empty() {
+        res.push('\n');
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunks(patch: &str) -> Vec<Hunk> {
+        parse(patch).unwrap().remove(0).hunks
+    }
+
+    #[test]
+    fn apply_replaces_lines() {
+        let patch = "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\n";
+
+        let res = apply("one\ntwo\nthree\n", &hunks(patch)).unwrap();
+
+        assert_eq!(res, "one\n2\nthree\n");
+    }
+
+    #[test]
+    fn apply_finds_moved_hunks() {
+        let patch =
+            "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,2 +1,3 @@\n one\n+one and a half\n two\n";
+
+        let res = apply("zero\none\ntwo\n", &hunks(patch)).unwrap();
+
+        assert_eq!(res, "zero\none\none and a half\ntwo\n");
+    }
+
+    #[test]
+    fn apply_inserts_into_empty_file() {
+        let patch = "--- /dev/null\n+++ b/notes.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n";
+
+        let res = apply("", &hunks(patch)).unwrap();
+
+        assert_eq!(res, "one\ntwo\n");
+    }
+
+    #[test]
+    fn apply_rejects_missing_lines() {
+        let patch = "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,1 +1,1 @@\n-four\n+4\n";
+
+        assert!(apply("one\ntwo\nthree\n", &hunks(patch)).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use reqwest::Method;
 use serde::{Deserialize, Serialize};
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     agents::tools::VizierTool,
     error::{VizierError, throw_vizier_error},
+    utils::{credential::CredentialVault, egress::EgressPolicy},
 };
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
@@ -18,6 +19,10 @@ pub struct HttpClientArgs {
     pub headers: HashMap<String, String>,
     #[schemars(description = "Request body (optional, for POST/PUT/PATCH)")]
     pub body: Option<String>,
+    #[schemars(
+        description = "name of a configured credential to authenticate with, see the tool description"
+    )]
+    pub credential: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
@@ -30,7 +35,16 @@ pub struct HttpClientOutput {
     pub headers: HashMap<String, String>,
 }
 
-pub struct HttpClient;
+pub struct HttpClient {
+    vault: Arc<CredentialVault>,
+    egress: Arc<EgressPolicy>,
+}
+
+impl HttpClient {
+    pub fn new(vault: Arc<CredentialVault>, egress: Arc<EgressPolicy>) -> Self {
+        Self { vault, egress }
+    }
+}
 
 #[async_trait::async_trait]
 impl VizierTool for HttpClient {
@@ -42,15 +56,22 @@ impl VizierTool for HttpClient {
     }
 
     fn description(&self) -> String {
-        "Make HTTP requests to interact with REST APIs. Construct your own headers and choose the appropriate HTTP method. Supports GET, POST, PUT, DELETE, PATCH, HEAD, and OPTIONS.".to_string()
+        format!(
+            "Make HTTP requests to interact with REST APIs. Construct your own headers and choose the appropriate HTTP method. Supports GET, POST, PUT, DELETE, PATCH, HEAD, and OPTIONS.{}",
+            self.vault.describe()
+        )
     }
 
     async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
         let method = Method::from_bytes(args.method.to_uppercase().as_bytes())
             .map_err(|e| VizierError(format!("Invalid HTTP method: {}", e)))?;
 
-        let client = reqwest::Client::new();
-        let mut request = client.request(method, &args.url);
+        let client = self
+            .egress
+            .client_for(&args.url, args.credential.as_deref())?;
+        let mut request =
+            self.vault
+                .request(&client, method, &args.url, args.credential.as_deref())?;
 
         for (key, value) in &args.headers {
             request = request.header(key, value);
@@ -71,15 +92,12 @@ impl VizierTool for HttpClient {
         let headers: HashMap<String, String> = response
             .headers()
             .iter()
-            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+            .map(|(k, v)| (k.to_string(), self.vault.redact(v.to_str().unwrap_or(""))))
             .collect();
 
-        let body = response.text().await;
-
-        if let Err(err) = body {
-            return throw_vizier_error("http_client: text error", err);
-        }
-        let body = body.unwrap();
+        let body = self.egress.read_text(response).await?;
+        // servers may echo the credential back
+        let body = self.vault.redact(&body);
 
         Ok(HttpClientOutput {
             status,
@@ -88,4 +106,3 @@ impl VizierTool for HttpClient {
         })
     }
 }
-
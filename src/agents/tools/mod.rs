@@ -8,16 +8,23 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     agents::tools::{
-        brave_search::{BraveSearch, NewsOnlySearch, WebOnlySearch},
+        artifact::{ReadArtifact, ToolArtifacts},
+        attachment::AttachFile,
         consult::{ConsultAgent, DelegateAgent},
+        custom::CustomTool,
         discord::new_discord_tools,
         fetch::FetchWebpage,
+        files::{
+            DEFAULT_MAX_FILE_BYTES, FileRoot, GrepFiles, ListFiles, PatchFile, ReadFile, WriteFile,
+        },
         http_client::HttpClient,
         notify::{
             DiscordDmPrimaryUser, NotifyPrimaryUser, TelegramDmPrimaryUser, WebUiNotifyPrimaryUser,
         },
-        ptc::ProgramaticSandbox,
+        openapi::load_openapi_tools,
+        ptc::{ProgramaticSandbox, PythonSessions, ResetSandbox},
         scheduler::{DeleteTask, GetTaskDetail, ListTask, ScheduleCronTask, ScheduleOneTimeTask},
+        search::{NewsOnlySearch, SearchKind, WebOnlySearch, WebSearch, search_provider},
         shared_document::init_shared_document_tools,
         shell::ShellExec,
         skill::CreateSkill,
@@ -30,21 +37,27 @@ use crate::{
             WritePrimaryDocument,
         },
     },
+    config::{agent::FileToolsRoot, shell::ShellConfig, tools::custom::CustomToolExecutor},
     dependencies::VizierDependencies,
     error::VizierError,
     mcp::{VizierMcp, VizierMcpClient},
-    schema::{AgentId, VizierResponse},
-    utils::agent_workspace,
+    schema::{AgentId, VizierResponse, VizierSession},
+    utils::{agent_workspace, credential::CredentialVault, egress::EgressPolicy},
 };
 
-mod brave_search;
+mod artifact;
+mod attachment;
 mod consult;
+mod custom;
 mod discord;
 mod fetch;
+mod files;
 mod http_client;
 mod notify;
+mod openapi;
 mod ptc;
 mod scheduler;
+mod search;
 mod shared_document;
 mod shell;
 mod skill;
@@ -56,6 +69,36 @@ mod workspace;
 
 type VizierToolDef = Arc<Box<dyn VizierToolDyn + Send + Sync + 'static>>;
 
+tokio::task_local! {
+    /// the session whose turn is calling the tools
+    static TOOL_SESSION: VizierSession;
+}
+
+/// runs the future with the session visible to the tools it calls
+pub async fn with_tool_session<F: Future>(session: Option<VizierSession>, future: F) -> F::Output {
+    match session {
+        Some(session) => TOOL_SESSION.scope(session, future).await,
+        None => future.await,
+    }
+}
+
+pub fn current_tool_session() -> Option<VizierSession> {
+    TOOL_SESSION.try_with(|session| session.clone()).ok()
+}
+
+fn is_allowed(allow: &[String], deny: &[String], function_name: &str) -> bool {
+    (allow.is_empty() || matches_tool(allow, function_name)) && !matches_tool(deny, function_name)
+}
+
+/// whether a tool name matches any of the patterns, `*` wildcards are allowed
+pub fn matches_tool(patterns: &[String], function_name: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        glob::Pattern::new(pattern)
+            .map(|pattern| pattern.matches(function_name))
+            .unwrap_or(false)
+    })
+}
+
 #[derive(Clone)]
 pub struct VizierToolSet {
     pub tools: HashMap<String, Arc<Box<dyn VizierToolDyn + Send + Sync + 'static>>>,
@@ -104,6 +147,9 @@ pub struct VizierTools {
     pub default_toolset: VizierToolSet,
     pub user_toolset: VizierToolSet,
     pub mcp: HashMap<String, Arc<VizierMcp>>,
+    allow: Vec<String>,
+    deny: Vec<String>,
+    artifacts: Option<Arc<ToolArtifacts>>,
 }
 
 #[async_trait::async_trait]
@@ -118,6 +164,12 @@ pub trait VizierToolDyn {
 
     fn output_schema(&self) -> serde_json::Value;
 
+    /// exclusive tools never run alongside other tool calls of the same turn
+    fn exclusive(&self) -> bool;
+
+    /// attachments of the tool response go to the user with the final answer
+    fn delivers_attachments(&self) -> bool;
+
     async fn tool_call(&self, args: String) -> Result<String, VizierError>;
 }
 
@@ -147,6 +199,14 @@ impl<Tool: VizierTool + Sync + Send> VizierToolDyn for Tool {
         Self::output_schema()
     }
 
+    fn exclusive(&self) -> bool {
+        Self::exclusive()
+    }
+
+    fn delivers_attachments(&self) -> bool {
+        Self::delivers_attachments()
+    }
+
     async fn tool_call(&self, args: String) -> Result<String, VizierError> {
         let input = serde_json::from_str(&args).map_err(|err| VizierError(err.to_string()))?;
         let output = self.call(input).await?;
@@ -170,6 +230,16 @@ pub trait VizierTool {
         serde_json::to_value(schema_for!(<Self as VizierTool>::Output)).unwrap()
     }
 
+    /// tools with side effects on shared state (shell, workspace files) should
+    /// not race with other calls
+    fn exclusive() -> bool {
+        false
+    }
+
+    fn delivers_attachments() -> bool {
+        false
+    }
+
     fn description(&self) -> String;
 
     async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError>;
@@ -194,11 +264,37 @@ impl VizierTools {
                 parameters: tool.parameters.clone(),
             }));
         }
+        res.retain(|tool| self.is_enabled(&tool.name));
 
         Ok(res)
     }
 
+    /// whether the agent's allow/deny lists let it use the tool
+    pub fn is_enabled(&self, function_name: &str) -> bool {
+        is_allowed(&self.allow, &self.deny, function_name)
+    }
+
+    pub fn is_exclusive(&self, function_name: &str) -> bool {
+        self.default_toolset
+            .get_tool(function_name.to_string())
+            .or_else(|_| self.user_toolset.get_tool(function_name.to_string()))
+            .map(|tool| tool.exclusive())
+            .unwrap_or(false)
+    }
+
+    pub fn delivers_attachments(&self, function_name: &str) -> bool {
+        self.default_toolset
+            .get_tool(function_name.to_string())
+            .or_else(|_| self.user_toolset.get_tool(function_name.to_string()))
+            .map(|tool| tool.delivers_attachments())
+            .unwrap_or(false)
+    }
+
     pub async fn call(&self, function_name: String, params: String) -> Result<VizierResponse> {
+        if !self.is_enabled(&function_name) {
+            return Err(VizierError(format!("{} is not available", function_name)).into());
+        }
+
         // mcp calls
         if function_name.starts_with("mcp_") {
             if let Some((server, function_name)) = function_name.split_once("__") {
@@ -247,6 +343,14 @@ impl VizierTools {
 
         Err(VizierError(format!("{} not found", function_name)).into())
     }
+
+    /// swaps a tool result above the agent's threshold for its artifact
+    pub async fn offload(&self, function_name: &str, res: VizierResponse) -> VizierResponse {
+        match &self.artifacts {
+            Some(artifacts) => artifacts.offload(function_name, res).await,
+            None => res,
+        }
+    }
 }
 
 impl VizierTools {
@@ -256,6 +360,8 @@ impl VizierTools {
         let workspace = deps.config.workspace.clone();
         let agent_workspace_path = agent_workspace(&workspace, &agent_id);
         let agent_workspace = agent_workspace_path.to_string_lossy().to_string();
+        let vault = Arc::new(CredentialVault::new(tool_config.credentials.clone()));
+        let egress = Arc::new(EgressPolicy::new(&agent_config.tools.egress));
 
         let mut default_toolset = VizierToolSet::new();
         let mut user_toolset = VizierToolSet::new();
@@ -303,6 +409,52 @@ impl VizierTools {
             default_toolset = default_toolset.tool(ShellExec(deps.shell.clone()));
         }
 
+        let artifacts = (agent_config.tools.artifacts.threshold > 0).then(|| {
+            Arc::new(ToolArtifacts::new(
+                agent_id.clone(),
+                deps.storage.clone(),
+                agent_config.tools.artifacts.clone(),
+            ))
+        });
+        if artifacts.is_some() {
+            default_toolset = default_toolset.tool(ReadArtifact {
+                agent_id: agent_id.clone(),
+                storage: deps.storage.clone(),
+                ttl: *agent_config.tools.artifacts.ttl,
+            });
+        }
+
+        let files = &agent_config.tools.files;
+        if files.enabled {
+            let root = match (&files.root, &deps.config.shell) {
+                (FileToolsRoot::Shell, ShellConfig::Local(local)) => local.path.clone().into(),
+                (FileToolsRoot::Shell, _) => {
+                    log::warn!("file tools can only use the path of a local shell");
+                    agent_workspace_path.clone()
+                }
+                (FileToolsRoot::Workspace, _) => agent_workspace_path.clone(),
+            };
+            let root = Arc::new(FileRoot::new(
+                root,
+                files.max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES),
+            )?);
+
+            default_toolset = default_toolset
+                .tool(ReadFile(root.clone()))
+                .tool(ListFiles(root.clone()))
+                .tool(GrepFiles(root.clone()));
+            if !files.read_only {
+                default_toolset = default_toolset
+                    .tool(WriteFile(root.clone()))
+                    .tool(PatchFile(root));
+            }
+        }
+
+        default_toolset = default_toolset.tool(AttachFile::new(
+            agent_workspace.clone(),
+            agent_config.tools.shell_access.then(|| deps.shell.clone()),
+        ));
+
         default_toolset = default_toolset
             .tool(DiscordDmPrimaryUser::new(deps.config.clone()))
             .tool(TelegramDmPrimaryUser::new(deps.config.clone()))
@@ -344,20 +496,35 @@ impl VizierTools {
             }
         }
 
-        if agent_config.tools.brave_search.enabled {
-            if let Some(brave_search) = tool_config.brave_search {
-                user_toolset = user_toolset
-                    .tool(BraveSearch::<WebOnlySearch>::new(&brave_search))
-                    .tool(BraveSearch::<NewsOnlySearch>::new(&brave_search));
+        let search_backend = match &agent_config.tools.web_search {
+            Some(name) => {
+                let backend = tool_config.search_backend(name);
+                if backend.is_none() {
+                    log::warn!("search backend {} is not configured", name);
+                }
+                backend
+            }
+            None if agent_config.tools.brave_search.enabled => tool_config.search_backend("brave"),
+            None => None,
+        };
+        if let Some(backend) = search_backend {
+            let provider = search_provider(&backend);
+            user_toolset = user_toolset.tool(WebSearch::<WebOnlySearch>::new(provider.clone()));
+            if provider.supports(SearchKind::News) {
+                user_toolset = user_toolset.tool(WebSearch::<NewsOnlySearch>::new(provider));
             }
         }
 
         if agent_config.tools.fetch.enabled {
-            user_toolset = user_toolset.tool(FetchWebpage);
+            user_toolset = user_toolset.tool(FetchWebpage::new(
+                vault.clone(),
+                egress.clone(),
+                deps.storage.clone(),
+            ));
         }
 
         if agent_config.tools.http_client.enabled {
-            user_toolset = user_toolset.tool(HttpClient);
+            user_toolset = user_toolset.tool(HttpClient::new(vault.clone(), egress.clone()));
         }
 
         if agent_config.tools.vector_memory.enabled {
@@ -381,6 +548,44 @@ impl VizierTools {
             .tool(shared_doc_get)
             .tool(shared_doc_list);
 
+        for tool in deps.config.tools.custom.values() {
+            if matches!(tool.executor, CustomToolExecutor::Shell { .. })
+                && !agent_config.tools.shell_access
+            {
+                log::warn!("custom tool {} needs shell access, skipped", tool.name);
+                continue;
+            }
+            if default_toolset.tools.contains_key(&tool.name)
+                || user_toolset.tools.contains_key(&tool.name)
+            {
+                log::warn!("custom tool {} clashes with a built-in tool", tool.name);
+                continue;
+            }
+
+            user_toolset = user_toolset.tool(CustomTool::new(
+                tool.clone(),
+                deps.shell.clone(),
+                agent_config.tools.python_limits.clone(),
+                egress.clone(),
+            ));
+        }
+
+        for api in &agent_config.tools.openapi {
+            let Some(config) = tool_config.openapi.get(api) else {
+                log::warn!("openapi document {} is not configured", api);
+                continue;
+            };
+
+            match load_openapi_tools(api, config, vault.clone(), egress.clone()) {
+                Ok(operations) => {
+                    for operation in operations {
+                        user_toolset = user_toolset.tool(operation);
+                    }
+                }
+                Err(err) => log::warn!("failed to load openapi document {}: {}", api, err),
+            }
+        }
+
         let mut mcp = HashMap::new();
         for m in &agent_config.tools.mcp_servers {
             if let Some(client) = deps.mcp_clients.clients.get(m) {
@@ -388,14 +593,42 @@ impl VizierTools {
             }
         }
 
+        let allow = agent_config.tools.allow.clone();
+        let deny = agent_config.tools.deny.clone();
+        default_toolset
+            .tools
+            .retain(|name, _| is_allowed(&allow, &deny, name));
+        user_toolset
+            .tools
+            .retain(|name, _| is_allowed(&allow, &deny, name));
+
         if agent_config.tools.programmatic_sandbox {
-            let ptc_toolset = VizierToolSet::new().tool(ProgramaticSandbox {
-                tools: Arc::new(user_toolset),
+            let user_toolset = Arc::new(user_toolset);
+            let python_sessions = &agent_config.tools.python_sessions;
+            let limits = agent_config.tools.python_limits.clone();
+            let sessions = python_sessions.enabled.then(|| {
+                PythonSessions::new(
+                    user_toolset.clone(),
+                    limits.clone(),
+                    *python_sessions.idle_timeout,
+                )
+            });
+
+            let mut ptc_toolset = VizierToolSet::new().tool(ProgramaticSandbox {
+                tools: user_toolset,
+                sessions: sessions.clone(),
+                limits,
             });
+            if let Some(sessions) = sessions {
+                ptc_toolset = ptc_toolset.tool(ResetSandbox(sessions));
+            }
             let tools = Self {
                 default_toolset: default_toolset.clone(),
                 user_toolset: ptc_toolset,
                 mcp: mcp.clone(),
+                allow,
+                deny,
+                artifacts,
             };
             return Ok(tools);
         }
@@ -404,6 +637,9 @@ impl VizierTools {
             default_toolset: default_toolset.clone(),
             user_toolset: user_toolset.clone(),
             mcp: mcp.clone(),
+            allow,
+            deny,
+            artifacts,
         };
         Ok(tools)
     }
@@ -0,0 +1,404 @@
+use std::sync::Arc;
+
+use reqwest::Method;
+use rig::completion::ToolDefinition;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    agents::tools::VizierToolDyn,
+    config::tools::openapi::OpenApiConfig,
+    error::{VizierError, throw_vizier_error},
+    utils::{
+        credential::{CredentialVault, authorize},
+        egress::EgressPolicy,
+    },
+};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+// recursive schemas are cut off past this depth
+const MAX_REF_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+enum ParamLocation {
+    Path,
+    Query,
+    Header,
+}
+
+/// one operation of an OpenAPI document
+pub struct OpenApiOperation {
+    name: String,
+    description: String,
+    method: Method,
+    path: String,
+    base_url: String,
+    params: Vec<(String, ParamLocation)>,
+    /// media type of the request body, the body goes in the `body` argument
+    body: Option<String>,
+    input: Value,
+    config: OpenApiConfig,
+    vault: Arc<CredentialVault>,
+    egress: Arc<EgressPolicy>,
+}
+
+/// builds a tool for every operation of the document, named `openapi_<api>__<operationId>`
+pub fn load_openapi_tools(
+    api: &str,
+    config: &OpenApiConfig,
+    vault: Arc<CredentialVault>,
+    egress: Arc<EgressPolicy>,
+) -> Result<Vec<OpenApiOperation>, VizierError> {
+    let raw = std::fs::read_to_string(&config.spec)
+        .map_err(|err| VizierError(format!("{}: {}", config.spec, err)))?;
+    // YAML parses JSON documents as well
+    let doc: Value = serde_yaml::from_str(&raw)
+        .map_err(|err| VizierError(format!("{}: {}", config.spec, err)))?;
+
+    let base_url = config
+        .base_url
+        .clone()
+        .or_else(|| doc["servers"][0]["url"].as_str().map(|url| url.to_string()))
+        .ok_or(VizierError(format!(
+            "{}: no base_url configured and no servers in the document",
+            config.spec
+        )))?;
+
+    let mut res = vec![];
+    let Some(paths) = doc["paths"].as_object() else {
+        return Ok(res);
+    };
+
+    for (path, item) in paths {
+        let item = resolve(&doc, item, 0);
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+
+            let operation_id = operation["operationId"]
+                .as_str()
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| format!("{}_{}", method, path));
+
+            res.push(OpenApiOperation::new(
+                &doc,
+                format!("openapi_{}__{}", api, sanitize(&operation_id)),
+                method,
+                path,
+                &item,
+                operation,
+                base_url.trim_end_matches('/').to_string(),
+                config.clone(),
+                vault.clone(),
+                egress.clone(),
+            ));
+        }
+    }
+
+    Ok(res)
+}
+
+/// tool names only take alphanumerics, `_` and `-`
+fn sanitize(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// inlines the local `$ref`s of a value
+fn resolve(doc: &Value, value: &Value, depth: usize) -> Value {
+    if depth > MAX_REF_DEPTH {
+        return json!({});
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(reference) = object.get("$ref").and_then(|r| r.as_str()) {
+                return match reference
+                    .strip_prefix('#')
+                    .and_then(|pointer| doc.pointer(pointer))
+                {
+                    Some(target) => resolve(doc, target, depth + 1),
+                    None => json!({}),
+                };
+            }
+
+            Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), resolve(doc, value, depth)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|value| resolve(doc, value, depth))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+impl OpenApiOperation {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        doc: &Value,
+        name: String,
+        method: &str,
+        path: &str,
+        item: &Value,
+        operation: &Value,
+        base_url: String,
+        config: OpenApiConfig,
+        vault: Arc<CredentialVault>,
+        egress: Arc<EgressPolicy>,
+    ) -> Self {
+        let operation = resolve(doc, operation, 0);
+
+        let mut properties = Map::new();
+        let mut required = vec![];
+        let mut params = vec![];
+
+        // operation parameters override the ones of the path
+        let path_params = item["parameters"].as_array().cloned().unwrap_or_default();
+        let operation_params = operation["parameters"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for param in path_params.iter().chain(operation_params.iter()) {
+            let Some(param_name) = param["name"].as_str() else {
+                continue;
+            };
+            let location = match param["in"].as_str() {
+                Some("path") => ParamLocation::Path,
+                Some("query") => ParamLocation::Query,
+                Some("header") => ParamLocation::Header,
+                _ => continue,
+            };
+
+            let mut schema = param.get("schema").cloned().unwrap_or(json!({}));
+            if let (Some(description), Value::Object(schema)) =
+                (param["description"].as_str(), &mut schema)
+            {
+                schema.insert("description".into(), description.into());
+            }
+            properties.insert(param_name.to_string(), schema);
+
+            if (location == ParamLocation::Path || param["required"].as_bool() == Some(true))
+                && !required.contains(&param_name.to_string())
+            {
+                required.push(param_name.to_string());
+            }
+
+            params.retain(|(name, _)| name != param_name);
+            params.push((param_name.to_string(), location));
+        }
+
+        let mut body = None;
+        if let Some(content) = operation["requestBody"]["content"].as_object() {
+            let media_type = if content.contains_key("application/json") {
+                Some("application/json".to_string())
+            } else {
+                content.keys().next().cloned()
+            };
+
+            if let Some(media_type) = media_type {
+                let mut schema = content[&media_type]["schema"].clone();
+                if schema.is_null() {
+                    schema = json!({});
+                }
+                if let (Some(description), Value::Object(schema)) = (
+                    operation["requestBody"]["description"].as_str(),
+                    &mut schema,
+                ) {
+                    schema.insert("description".into(), description.into());
+                }
+                properties.insert("body".into(), schema);
+
+                if operation["requestBody"]["required"].as_bool() == Some(true) {
+                    required.push("body".into());
+                }
+                body = Some(media_type);
+            }
+        }
+
+        let summary = [
+            operation["summary"].as_str(),
+            operation["description"].as_str(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+        Self {
+            name,
+            description: format!("`{} {}`\n\n{}", method.to_uppercase(), path, summary)
+                .trim()
+                .to_string(),
+            method: Method::from_bytes(method.to_uppercase().as_bytes()).unwrap(),
+            path: path.to_string(),
+            base_url,
+            params,
+            body,
+            input: json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            }),
+            config,
+            vault,
+            egress,
+        }
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, VizierError> {
+        let mut path = self.path.clone();
+        let mut query = vec![];
+        let mut headers = self.config.headers.clone().into_iter().collect::<Vec<_>>();
+
+        for (name, location) in &self.params {
+            let Some(value) = args.get(name).filter(|value| !value.is_null()) else {
+                continue;
+            };
+
+            match location {
+                ParamLocation::Path => {
+                    // form encoding turns spaces into `+`, paths want `%20`
+                    let encoded = form_urlencoded::byte_serialize(as_text(value).as_bytes())
+                        .collect::<String>()
+                        .replace('+', "%20");
+                    path = path.replace(&format!("{{{}}}", name), &encoded);
+                }
+                ParamLocation::Query => match value {
+                    Value::Array(items) => {
+                        query.extend(items.iter().map(|item| (name.clone(), as_text(item))))
+                    }
+                    value => query.push((name.clone(), as_text(value))),
+                },
+                ParamLocation::Header => headers.push((name.clone(), as_text(value))),
+            }
+        }
+
+        // `..` would be normalized into a walk up the api's paths
+        if path
+            .split('/')
+            .any(|segment| segment == "." || segment == "..")
+        {
+            return Err(VizierError(format!("invalid path: {}", path)));
+        }
+
+        let mut url = format!("{}{}", self.base_url, path);
+        if !query.is_empty() {
+            let query =
+                serde_urlencoded::to_string(&query).map_err(|err| VizierError(err.to_string()))?;
+            url = format!("{}?{}", url, query);
+        }
+
+        // authenticated requests don't follow redirects to other hosts
+        let credential = match &self.config.auth {
+            Some(_) => Some(self.name.as_str()),
+            None => self.config.credential.as_deref(),
+        };
+        let client = self.egress.client_for(&url, credential)?;
+        let mut request = match &self.config.auth {
+            Some(auth) => authorize(&client, self.method.clone(), &url, auth)?,
+            None => self.vault.request(
+                &client,
+                self.method.clone(),
+                &url,
+                self.config.credential.as_deref(),
+            )?,
+        };
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+
+        if let (Some(media_type), Some(body)) = (&self.body, args.get("body")) {
+            let body = match body {
+                Value::String(body) if media_type != "application/json" => body.clone(),
+                body => body.to_string(),
+            };
+            request = request.header("Content-Type", media_type).body(body);
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => return throw_vizier_error("openapi tool: request error", err),
+        };
+        let status = response.status();
+        let text = self.vault.redact(&self.egress.read_text(response).await?);
+
+        if !status.is_success() {
+            return Err(VizierError(format!("{}: {}", status, text)));
+        }
+
+        // structured outputs stay structured
+        Ok(serde_json::from_str(&text).unwrap_or(Value::String(text)))
+    }
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[async_trait::async_trait]
+impl VizierToolDyn for OpenApiOperation {
+    fn tool_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn tool_def(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: self.input.clone(),
+        }
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn input_schema(&self) -> Value {
+        self.input.clone()
+    }
+
+    fn output_schema(&self) -> Value {
+        json!({})
+    }
+
+    fn exclusive(&self) -> bool {
+        false
+    }
+
+    fn delivers_attachments(&self) -> bool {
+        false
+    }
+
+    async fn tool_call(&self, args: String) -> Result<String, VizierError> {
+        let args = serde_json::from_str(&args).map_err(|err| VizierError(err.to_string()))?;
+        let output = self.execute(args).await?;
+
+        serde_json::to_string(&output).map_err(|err| VizierError(err.to_string()))
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use rustpython_vm::{
+    AsObject, PyObjectRef, PyResult, VirtualMachine, common::rc::PyRc, function::FuncArgs,
+    scope::Scope, signal::UserSignalSender,
+};
+
+use crate::config::agent::PythonLimitsConfig;
+
+const INTERRUPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// the modules that lead to the rest of the interpreter
+const INTERNAL_MODULES: &[&str] = &[
+    "sys",
+    "builtins",
+    "importlib",
+    "_imp",
+    "_frozen_importlib",
+    "_frozen_importlib_external",
+    "ctypes",
+    "_ctypes",
+    "gc",
+    "signal",
+    "_signal",
+    "_thread",
+    "threading",
+];
+
+const FILESYSTEM_MODULES: &[&str] = &[
+    "os",
+    "posix",
+    "nt",
+    "_os",
+    "io",
+    "_io",
+    "pathlib",
+    "shutil",
+    "glob",
+    "tempfile",
+    "fileinput",
+    "linecache",
+    "subprocess",
+    "_posixsubprocess",
+    "fcntl",
+    "mmap",
+    "pty",
+    "resource",
+    "sqlite3",
+    "_sqlite3",
+    "zipfile",
+    "tarfile",
+];
+
+const NETWORK_MODULES: &[&str] = &[
+    "socket",
+    "_socket",
+    "ssl",
+    "_ssl",
+    "select",
+    "selectors",
+    "asyncio",
+    "http",
+    "urllib",
+    "ftplib",
+    "smtplib",
+    "poplib",
+    "imaplib",
+    "socketserver",
+    "xmlrpc",
+    "webbrowser",
+];
+
+/// the modules the interpreter can't start without, they stay loaded even
+/// when blocked
+const STARTUP_MODULES: &[&str] = &[
+    "sys",
+    "builtins",
+    "_imp",
+    "_frozen_importlib",
+    "_frozen_importlib_external",
+    "_io",
+    "posix",
+    "nt",
+    "_signal",
+    "_thread",
+];
+
+/// the `posix` functions the import system and the common modules need, the
+/// rest fail without filesystem access
+const FILESYSTEM_FUNCTIONS: &[&str] = &[
+    "fspath",
+    "getcwd",
+    "listdir",
+    "stat",
+    "lstat",
+    "fstat",
+    "_path_splitroot",
+    "strerror",
+    "urandom",
+    "uname",
+    "getpid",
+    "getppid",
+    "getuid",
+    "geteuid",
+    "getgid",
+    "getegid",
+    "cpu_count",
+    "get_terminal_size",
+    "times",
+    "register_at_fork",
+];
+
+/// what a script sent to `output`, up to the limit
+#[derive(Default)]
+pub struct Console {
+    lines: Vec<String>,
+    chars: usize,
+    max_chars: usize,
+    truncated: bool,
+}
+
+impl Console {
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        let mut line = line;
+        let left = self.max_chars.saturating_sub(self.chars);
+        if line.chars().count() > left {
+            self.truncated = true;
+            line = line.chars().take(left).collect();
+            if line.is_empty() {
+                return;
+            }
+        }
+
+        self.chars += line.chars().count() + 1;
+        self.lines.push(line);
+    }
+
+    /// the output so far and whether some of it was dropped
+    pub fn take(&mut self) -> (String, bool) {
+        let output = std::mem::take(&mut self.lines).join("\n");
+        let truncated = self.truncated;
+        self.chars = 0;
+        self.truncated = false;
+
+        (output, truncated)
+    }
+}
+
+/// raises a `TimeoutError` in the script once it runs out of time, again and
+/// again so catching it doesn't keep the script going
+pub struct Watchdog {
+    done: Option<flume::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+    running: Arc<AtomicBool>,
+    timed_out: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+    pub fn start(signals: Arc<UserSignalSender>, timeout: Duration) -> Self {
+        let (done, finished) = flume::bounded::<()>(1);
+        let running = Arc::new(AtomicBool::new(true));
+        let timed_out = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let running = running.clone();
+            let timed_out = timed_out.clone();
+            std::thread::spawn(move || {
+                let mut wait = timeout;
+                while let Err(flume::RecvTimeoutError::Timeout) = finished.recv_timeout(wait) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    wait = INTERRUPT_INTERVAL;
+
+                    // a signal still queued once the script is done is a no-op
+                    let running = running.clone();
+                    let _ = signals.send(Box::new(move |vm: &VirtualMachine| {
+                        if !running.load(Ordering::SeqCst) {
+                            return Ok(());
+                        }
+                        Err(vm.new_exception_msg(
+                            vm.ctx.exceptions.timeout_error.to_owned(),
+                            "the script ran out of time".to_owned(),
+                        ))
+                    }));
+                }
+            })
+        };
+
+        Self {
+            done: Some(done),
+            thread: Some(thread),
+            running,
+            timed_out,
+        }
+    }
+
+    /// stops the watchdog, true when the script ran out of time
+    pub fn stop(mut self) -> bool {
+        self.running.store(false, Ordering::SeqCst);
+        drop(self.done.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+
+        self.timed_out.load(Ordering::SeqCst)
+    }
+}
+
+fn blocked_modules(limits: &PythonLimitsConfig) -> Vec<&'static str> {
+    if limits.allow_filesystem && limits.allow_network {
+        return vec![];
+    }
+
+    let mut blocked = INTERNAL_MODULES.to_vec();
+    if !limits.allow_filesystem {
+        blocked.extend(FILESYSTEM_MODULES);
+    }
+    if !limits.allow_network {
+        blocked.extend(NETWORK_MODULES);
+    }
+    blocked
+}
+
+/// drops the blocked native modules before the interpreter starts, so they
+/// can't be reached through the stdlib either
+pub fn drop_native_modules(vm: &mut VirtualMachine, limits: &PythonLimitsConfig) {
+    let blocked = blocked_modules(limits);
+    if let Some(state) = PyRc::get_mut(&mut vm.state) {
+        state
+            .module_inits
+            .retain(|name, _| STARTUP_MODULES.contains(&&**name) || !blocked.contains(&&**name));
+    }
+}
+
+fn no_filesystem(vm: &VirtualMachine, name: &'static str) -> PyObjectRef {
+    vm.new_function(name, |_args: FuncArgs, vm: &VirtualMachine| -> PyResult {
+        Err(vm.new_exception_msg(
+            vm.ctx.exceptions.permission_error.to_owned(),
+            "the sandbox has no filesystem access".to_owned(),
+        ))
+    })
+    .into()
+}
+
+/// imports of the blocked modules fail, and without filesystem access `open`
+/// and the `posix` functions that write, spawn or read outside the import
+/// system fail too. only the script's own imports are checked, the stdlib
+/// still imports what it needs. it keeps honest scripts in bounds, it's not a
+/// security boundary
+pub fn restrict(vm: &VirtualMachine, scope: &Scope, limits: &PythonLimitsConfig) -> PyResult<()> {
+    let blocked = blocked_modules(limits);
+    if blocked.is_empty() {
+        return Ok(());
+    }
+
+    let modules = vm.sys_module.get_attr("modules", vm)?;
+    for module in blocked
+        .iter()
+        .filter(|module| !STARTUP_MODULES.contains(module))
+    {
+        let _ = modules.del_item(*module, vm);
+    }
+
+    let builtins = vm.builtins.dict();
+    let import = builtins.get_item("__import__", vm)?;
+    let globals = scope.globals.clone();
+    let restricted_import = vm.new_function(
+        "__import__",
+        move |args: FuncArgs, vm: &VirtualMachine| -> PyResult {
+            let name = match args.args.first() {
+                Some(name) => name.str(vm)?.as_str().to_string(),
+                None => return import.call(args, vm),
+            };
+            let from_script = args
+                .args
+                .get(1)
+                .or(args.kwargs.get("globals"))
+                .is_some_and(|importer| importer.is(&globals));
+            let root = name.split('.').next().unwrap_or_default();
+
+            if from_script && blocked.contains(&root) {
+                return Err(vm.new_exception_msg(
+                    vm.ctx.exceptions.import_error.to_owned(),
+                    format!("module {} is not available in the sandbox", name),
+                ));
+            }
+
+            import.call(args, vm)
+        },
+    );
+    builtins.set_item("__import__", restricted_import.into(), vm)?;
+
+    if !limits.allow_filesystem {
+        builtins.set_item("open", no_filesystem(vm, "open"), vm)?;
+        if let Some(io) = vm.import("_io", 0)?.dict() {
+            io.set_item("open", no_filesystem(vm, "open"), vm)?;
+        }
+
+        // `os` copies these when it's first imported, and the stdlib reaches
+        // `posix` through it
+        if let Some(posix) = vm.import("posix", 0)?.dict() {
+            let denied = (&posix)
+                .into_iter()
+                .filter(|(name, value)| {
+                    value
+                        .class()
+                        .is(vm.ctx.types.builtin_function_or_method_type)
+                        && !name
+                            .str(vm)
+                            .is_ok_and(|name| FILESYSTEM_FUNCTIONS.contains(&name.as_str()))
+                })
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            for name in denied {
+                posix.set_item(&*name, no_filesystem(vm, "denied"), vm)?;
+            }
+        }
+    }
+
+    Ok(())
+}
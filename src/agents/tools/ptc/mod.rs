@@ -1,19 +1,35 @@
 use std::sync::{Arc, Mutex};
 
 use rustpython;
-use rustpython_vm::{self as vm, VirtualMachine};
+use rustpython_vm::{
+    self as vm, Interpreter, VirtualMachine, scope::Scope, signal::UserSignalSender,
+};
 use serde::{Deserialize, Serialize};
 use tokio::runtime::Handle;
 
 use crate::{
-    agents::tools::{VizierTool, VizierToolSet, ptc::converter::json_to_py},
+    agents::tools::{
+        VizierTool, VizierToolSet, current_tool_session,
+        ptc::{
+            converter::json_to_py,
+            limits::{Console, Watchdog, drop_native_modules, restrict},
+        },
+    },
+    config::agent::PythonLimitsConfig,
     error::VizierError,
 };
 
 mod converter;
+mod limits;
+mod session;
+
+pub use session::{PythonSessions, ResetSandbox};
 
 pub struct ProgramaticSandbox {
     pub tools: Arc<VizierToolSet>,
+    /// interpreters kept per session, a fresh one for every call when unset
+    pub sessions: Option<Arc<PythonSessions>>,
+    pub limits: PythonLimitsConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
@@ -26,6 +42,30 @@ pub struct ProgramaticSandboxArgs {
 pub struct ProgramaticSandboxOutput {
     #[schemars(description = "console_output")]
     pub console_outputs: String,
+    #[schemars(description = "the output went over the limit, the rest was dropped")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[schemars(description = "why the script stopped, what it sent to output before stays")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<SandboxError>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxErrorKind {
+    Syntax,
+    Runtime,
+    Timeout,
+    /// the limits couldn't be applied, the script didn't run
+    Setup,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct SandboxError {
+    pub kind: SandboxErrorKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceback: Option<String>,
 }
 
 #[async_trait::async_trait]
@@ -56,8 +96,33 @@ impl VizierTool for ProgramaticSandbox {
 
         let examples = r#"tool_call("web_search", "{ \"query\": \"some query\", \"page\": 1 }")"#;
 
+        let state = match self.sessions {
+            Some(_) => {
+                "Variables, functions and imports are kept between the scripts of this conversation, like a notebook. Use reset_sandbox to start over."
+            }
+            None => "Every script starts from a clean interpreter.",
+        };
+
+        let mut unavailable = vec![];
+        if !self.limits.allow_filesystem {
+            unavailable.push("filesystem");
+        }
+        if !self.limits.allow_network {
+            unavailable.push("network");
+        }
+        let mut limits = format!(
+            "A script may run for {} and output {} characters.",
+            self.limits.timeout, self.limits.max_output_chars
+        );
+        if !unavailable.is_empty() {
+            limits.push_str(&format!(
+                " The {} modules and sys are not available.",
+                unavailable.join(" and ")
+            ));
+        }
+
         format!(
-            r#"Run a Python script in a sandboxed environment.
+            r#"Run a Python script in a sandboxed environment. {state} {limits}
 
 Available functions:
 - output(str): Print string (and only accept string) to output, you need to use this to get or format the result of tool_call from console output, **do not use print()**
@@ -77,56 +142,211 @@ All tool_call results are serialized as JSON strings matching the output schema.
     }
 
     async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
-        let script = args.script.clone();
-        let tools = self.tools.clone();
+        match (&self.sessions, current_tool_session()) {
+            (Some(sessions), Some(session)) => sessions.run(&session, args.script).await,
+            _ => {
+                let tools = self.tools.clone();
+                let limits = self.limits.clone();
+                let handle = Handle::current();
+                tokio::task::spawn_blocking(move || {
+                    Sandbox::new(tools, handle, limits).run(&args.script)
+                })
+                .await
+                .map_err(|err| VizierError(err.to_string()))
+            }
+        }
+    }
+}
+
+/// runs a script with `output` and `tool_call` (over the given tools), and
+/// `args` when there are any, off the runtime and within the limits. returns
+/// what the script sent to `output`, followed by the traceback when it failed
+pub async fn run_script(
+    script: String,
+    tools: Arc<VizierToolSet>,
+    args: Option<serde_json::Value>,
+    limits: PythonLimitsConfig,
+) -> Result<String, VizierError> {
+    let handle = Handle::current();
+    let output = tokio::task::spawn_blocking(move || {
+        let sandbox = Sandbox::new(tools, handle, limits);
+        if let Some(args) = &args {
+            sandbox.set_global("args", args);
+        }
 
-        let console = Arc::new(Mutex::new(vec![]));
+        sandbox.run(&script)
+    })
+    .await
+    .map_err(|err| VizierError(err.to_string()))?;
+
+    Ok(match output.error {
+        Some(error) => [
+            output.console_outputs,
+            error.traceback.unwrap_or(error.message),
+        ]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n"),
+        None => output.console_outputs,
+    })
+}
 
-        let vm_console = console.clone();
+/// an interpreter and the scope its scripts share, it can't leave its thread
+struct Sandbox {
+    interpreter: Interpreter,
+    scope: Scope,
+    console: Arc<Mutex<Console>>,
+    signals: Arc<UserSignalSender>,
+    limits: PythonLimitsConfig,
+    /// why the limits couldn't be applied, scripts don't run without them
+    setup_error: Option<String>,
+}
+
+impl Sandbox {
+    fn new(tools: Arc<VizierToolSet>, handle: Handle, limits: PythonLimitsConfig) -> Self {
+        let (signals, signal_receiver) = vm::signal::user_signal_channel();
+        let native_limits = limits.clone();
         let interpreter = rustpython::InterpreterConfig::new()
             .init_stdlib()
+            .init_hook(Box::new(move |vm| {
+                vm.set_user_signal_channel(signal_receiver);
+                drop_native_modules(vm, &native_limits);
+            }))
             .interpreter();
+        let console = Arc::new(Mutex::new(Console::new(limits.max_output_chars)));
 
-        interpreter.enter(|vm| {
-            let scope: vm::scope::Scope = vm.new_scope_with_builtins();
-            let tools = tools.clone();
-            let print = vm.new_function("print", move |str: String| {
-                println!(">> {str}");
-                vm_console.lock().unwrap().push(str);
+        let (scope, setup_error) = interpreter.enter(|vm| {
+            let scope = sandbox_scope(vm, tools, console.clone(), handle);
+            let setup_error = restrict(vm, &scope, &limits).err().map(|exc| {
+                let mut message = String::new();
+                let _ = vm.write_exception(&mut message, &exc);
+                log::error!("sandbox limits: {}", message.trim_end());
+                message.trim_end().to_string()
             });
+            (scope, setup_error)
+        });
 
-            let tool_call = vm.new_function(
-                "tool_call",
-                move |function_name: String, params: String, vm: &VirtualMachine| {
-                    let tool_call = tools.call(function_name, params);
-                    let handle = Handle::try_current().unwrap();
-                    // // We're inside a tokio runtime, use block_in_place
-                    let result = tokio::task::block_in_place(|| {
-                        match handle.block_on(async { tool_call.await }) {
-                            Ok(val) => val,
-                            Err(err) => serde_json::Value::String(err.to_string()),
-                        }
-                    });
-
-                    return json_to_py(&result, &vm);
-                },
-            );
-
-            let _ = scope.globals.set_item("output", print.into(), vm);
-            let _ = scope.globals.set_item("tool_call", tool_call.into(), vm);
-
-            let code_obj = vm
-                .compile(&script, vm::compiler::Mode::Exec, "<embedded>".to_owned())
-                .map_err(|err| vm.new_syntax_error(&err, Some(&script)))
-                .unwrap();
-
-            let _ = vm.run_code_obj(code_obj, scope.clone());
+        Self {
+            interpreter,
+            scope,
+            console,
+            signals: Arc::new(signals),
+            limits,
+            setup_error,
+        }
+    }
+
+    fn set_global(&self, name: &str, value: &serde_json::Value) {
+        self.interpreter.enter(|vm| {
+            let _ = self.scope.globals.set_item(name, json_to_py(value, vm), vm);
         });
+    }
+
+    fn run(&self, script: &str) -> ProgramaticSandboxOutput {
+        if let Some(setup_error) = &self.setup_error {
+            return ProgramaticSandboxOutput {
+                console_outputs: String::new(),
+                truncated: false,
+                error: Some(SandboxError {
+                    kind: SandboxErrorKind::Setup,
+                    message: "the sandbox limits couldn't be applied".into(),
+                    traceback: Some(setup_error.clone()),
+                }),
+            };
+        }
+
+        let watchdog = Watchdog::start(self.signals.clone(), *self.limits.timeout);
+
+        let error = self
+            .interpreter
+            .enter(|vm| execute(vm, self.scope.clone(), script));
 
-        drop(interpreter);
+        let error = match watchdog.stop() {
+            true => Some(SandboxError {
+                kind: SandboxErrorKind::Timeout,
+                message: format!("the script ran longer than {}", self.limits.timeout),
+                traceback: error.and_then(|error| error.traceback),
+            }),
+            false => error,
+        };
 
-        Ok(ProgramaticSandboxOutput {
-            console_outputs: console.lock().unwrap().join("\n"),
-        })
+        let (console_outputs, truncated) = self.console.lock().unwrap().take();
+        ProgramaticSandboxOutput {
+            console_outputs,
+            truncated,
+            error,
+        }
     }
 }
+
+/// a scope with `output` writing to the console and `tool_call` calling the
+/// given tools
+fn sandbox_scope(
+    vm: &VirtualMachine,
+    tools: Arc<VizierToolSet>,
+    console: Arc<Mutex<Console>>,
+    handle: Handle,
+) -> Scope {
+    let scope = vm.new_scope_with_builtins();
+    let print = vm.new_function("print", move |str: String| {
+        log::debug!("[Sandbox]: {str}");
+        console.lock().unwrap().push(str);
+    });
+
+    let tool_call = vm.new_function(
+        "tool_call",
+        move |function_name: String, params: String, vm: &VirtualMachine| {
+            let tool_call = tools.call(function_name, params);
+            let tool_call = async {
+                match tool_call.await {
+                    Ok(val) => val,
+                    Err(err) => serde_json::Value::String(err.to_string()),
+                }
+            };
+            // session interpreters run on their own thread, outside the runtime
+            let result = match Handle::try_current() {
+                Ok(_) => tokio::task::block_in_place(|| handle.block_on(tool_call)),
+                Err(_) => handle.block_on(tool_call),
+            };
+
+            return json_to_py(&result, &vm);
+        },
+    );
+
+    let _ = scope.globals.set_item("output", print.into(), vm);
+    let _ = scope.globals.set_item("tool_call", tool_call.into(), vm);
+
+    scope
+}
+
+/// runs the script, returns why it failed
+fn execute(vm: &VirtualMachine, scope: Scope, script: &str) -> Option<SandboxError> {
+    let code_obj = match vm.compile(script, vm::compiler::Mode::Exec, "<sandbox>".to_owned()) {
+        Ok(code_obj) => code_obj,
+        Err(err) => {
+            return Some(SandboxError {
+                kind: SandboxErrorKind::Syntax,
+                message: err.to_string(),
+                traceback: None,
+            });
+        }
+    };
+
+    let exc = vm.run_code_obj(code_obj, scope).err()?;
+    let mut traceback = String::new();
+    let _ = vm.write_exception(&mut traceback, &exc);
+    let traceback = traceback.trim_end().to_string();
+
+    // the last line of the traceback is `NameError: name 'x' is not defined`
+    let message = match traceback.lines().last() {
+        Some(line) => line.to_string(),
+        None => "the script raised an exception".to_string(),
+    };
+
+    Some(SandboxError {
+        kind: SandboxErrorKind::Runtime,
+        message,
+        traceback: (!traceback.is_empty()).then_some(traceback),
+    })
+}
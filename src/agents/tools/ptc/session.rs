@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Handle;
+
+use crate::{
+    agents::tools::{
+        VizierTool, VizierToolSet, current_tool_session,
+        ptc::{ProgramaticSandboxOutput, Sandbox},
+    },
+    config::agent::PythonLimitsConfig,
+    error::VizierError,
+    schema::VizierSession,
+};
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Job {
+    script: String,
+    reply: flume::Sender<ProgramaticSandboxOutput>,
+}
+
+/// an interpreter living on its own thread, the vm can't leave it
+struct PythonSession {
+    jobs: flume::Sender<Job>,
+    last_used: Instant,
+}
+
+impl PythonSession {
+    fn spawn(tools: Arc<VizierToolSet>, limits: PythonLimitsConfig) -> Self {
+        let (jobs, queue) = flume::unbounded::<Job>();
+        let handle = Handle::current();
+
+        std::thread::spawn(move || {
+            let sandbox = Sandbox::new(tools, handle, limits);
+
+            // ends once the session is dropped
+            while let Ok(job) = queue.recv() {
+                let _ = job.reply.send(sandbox.run(&job.script));
+            }
+        });
+
+        Self {
+            jobs,
+            last_used: Instant::now(),
+        }
+    }
+}
+
+/// the sandbox interpreters of the agent's sessions, dropped once idle
+pub struct PythonSessions {
+    tools: Arc<VizierToolSet>,
+    limits: PythonLimitsConfig,
+    idle_timeout: Duration,
+    sessions: Mutex<HashMap<String, PythonSession>>,
+}
+
+impl PythonSessions {
+    pub fn new(
+        tools: Arc<VizierToolSet>,
+        limits: PythonLimitsConfig,
+        idle_timeout: Duration,
+    ) -> Arc<Self> {
+        let sessions = Arc::new(Self {
+            tools,
+            limits,
+            idle_timeout,
+            sessions: Mutex::new(HashMap::new()),
+        });
+
+        let sweeper = Arc::downgrade(&sessions);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(sessions) = Weak::upgrade(&sweeper) else {
+                    break;
+                };
+                sessions.evict_idle();
+            }
+        });
+
+        sessions
+    }
+
+    fn evict_idle(&self) {
+        let now = Instant::now();
+        self.sessions
+            .lock()
+            .unwrap()
+            .retain(|_, session| now.duration_since(session.last_used) < self.idle_timeout);
+    }
+
+    pub async fn run(
+        &self,
+        session: &VizierSession,
+        script: String,
+    ) -> Result<ProgramaticSandboxOutput, VizierError> {
+        let jobs = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let python = sessions
+                .entry(session.to_slug())
+                .or_insert_with(|| PythonSession::spawn(self.tools.clone(), self.limits.clone()));
+            python.last_used = Instant::now();
+            python.jobs.clone()
+        };
+
+        let (reply, output) = flume::bounded(1);
+        let sent = jobs.send_async(Job { script, reply }).await;
+        let output = match sent {
+            Ok(_) => output.recv_async().await.ok(),
+            Err(_) => None,
+        };
+
+        // the interpreter died, the next call starts a new one
+        output.ok_or_else(|| {
+            self.reset(session);
+            VizierError("the python session stopped, its state is lost".into())
+        })
+    }
+
+    pub fn reset(&self, session: &VizierSession) -> bool {
+        self.sessions
+            .lock()
+            .unwrap()
+            .remove(&session.to_slug())
+            .is_some()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct ResetSandboxArgs {}
+
+pub struct ResetSandbox(pub Arc<PythonSessions>);
+
+#[async_trait::async_trait]
+impl VizierTool for ResetSandbox {
+    type Input = ResetSandboxArgs;
+    type Output = String;
+
+    fn name() -> String {
+        "reset_sandbox".to_string()
+    }
+
+    fn description(&self) -> String {
+        "drop the variables, functions and imports of the programmatic sandbox, the next script starts from a clean interpreter".into()
+    }
+
+    async fn call(&self, _args: Self::Input) -> Result<Self::Output, VizierError> {
+        let session = current_tool_session()
+            .ok_or(VizierError("the sandbox has no session to reset".into()))?;
+
+        Ok(if self.0.reset(&session) {
+            "the sandbox is reset".into()
+        } else {
+            "the sandbox has no state yet".into()
+        })
+    }
+}
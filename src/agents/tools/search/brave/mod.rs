@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::{
+    agents::tools::search::{PAGE_SIZE, SearchKind, SearchProvider, SearchQuery, SearchResult},
+    config::tools::BraveSearchConfig,
+    error::{VizierError, throw_vizier_error},
+};
+
+mod request;
+mod response;
+
+const SEARCH_URL: &str = r"https://api.search.brave.com/res/v1/web/search";
+
+pub struct BraveSearch {
+    api_key: String,
+    safesearch: bool,
+}
+
+impl BraveSearch {
+    pub fn new(config: &BraveSearchConfig) -> Self {
+        Self {
+            api_key: config.api_key.clone(),
+            safesearch: config.safesearch,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for BraveSearch {
+    fn supports(&self, _kind: SearchKind) -> bool {
+        true
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, VizierError> {
+        let params = request::SearchParams {
+            q: query.query.clone(),
+            count: Some(PAGE_SIZE),
+            offset: Some(query.page.saturating_sub(1).saturating_mul(PAGE_SIZE)),
+            safesearch: Some(if self.safesearch { "strict" } else { "off" }.to_string()),
+            result_filter: Some(
+                match query.kind {
+                    SearchKind::Web => "web",
+                    SearchKind::News => "news",
+                }
+                .to_string(),
+            ),
+        };
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("{SEARCH_URL}?{}", params.to_url()))
+            .header("X-Subscription-Token", self.api_key.clone())
+            .header("Content-Type", "application/json")
+            .send()
+            .await;
+
+        if let Err(err) = response {
+            return throw_vizier_error("brave_search: http error", err);
+        }
+
+        let response = response.unwrap();
+        if response.status() != StatusCode::OK {
+            return throw_vizier_error("status error:", response.error_for_status().err().unwrap());
+        }
+
+        let text = response.text().await;
+        if let Err(err) = text {
+            return throw_vizier_error("brave_search: text error:", err);
+        }
+
+        let text = text.unwrap();
+
+        // throttle before return
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let response = match serde_json::from_str::<response::BraveResponse>(&text) {
+            Ok(value) => value,
+            Err(err) => return throw_vizier_error("brave_search: parse error:", err),
+        };
+
+        let web = response
+            .web
+            .map(|web| web.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|result| SearchResult {
+                title: result.title,
+                url: result.url,
+                description: result.description,
+                published: result.page_age,
+                source: result.profile.map(|profile| profile.name),
+            });
+        let news = response
+            .news
+            .map(|news| news.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|result| SearchResult {
+                title: result.title,
+                url: result.url,
+                description: result.description,
+                published: result.age,
+                source: result.source,
+            });
+
+        Ok(web.chain(news).collect())
+    }
+}
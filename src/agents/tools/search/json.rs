@@ -0,0 +1,103 @@
+use serde_json::Value;
+
+use crate::{
+    agents::tools::search::{PAGE_SIZE, SearchKind, SearchProvider, SearchQuery, SearchResult},
+    config::tools::search::JsonSearchConfig,
+    error::{VizierError, throw_vizier_error},
+};
+
+/// any search API answering with JSON, mapped with JSON pointers
+pub struct JsonSearch {
+    config: JsonSearchConfig,
+}
+
+impl JsonSearch {
+    pub fn new(config: &JsonSearchConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    fn url(&self, query: &SearchQuery) -> String {
+        let encode =
+            |value: &str| -> String { form_urlencoded::byte_serialize(value.as_bytes()).collect() };
+
+        let offset = query.page.saturating_sub(1).saturating_mul(PAGE_SIZE);
+        [
+            ("{{query}}", encode(&query.query)),
+            ("{{page}}", query.page.to_string()),
+            ("{{offset}}", offset.to_string()),
+            ("{{count}}", PAGE_SIZE.to_string()),
+        ]
+        .into_iter()
+        .fold(self.config.url.clone(), |url, (placeholder, value)| {
+            url.replace(placeholder, &value)
+        })
+    }
+}
+
+fn text_at(value: &Value, pointer: &str) -> Option<String> {
+    match value.pointer(pointer)? {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for JsonSearch {
+    fn supports(&self, kind: SearchKind) -> bool {
+        kind == SearchKind::Web
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, VizierError> {
+        let client = reqwest::Client::new();
+        let mut request = client.get(self.url(query));
+        for (key, value) in &self.config.headers {
+            request = request.header(key, value);
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => return throw_vizier_error("json search: http error", err),
+        };
+        let status = response.status();
+        if !status.is_success() {
+            log::error!("json search: status error: {}", status);
+            return Err(VizierError(format!(
+                "json search: status error: {}",
+                status
+            )));
+        }
+
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(err) => return throw_vizier_error("json search: text error", err),
+        };
+        let body = match serde_json::from_str::<Value>(&text) {
+            Ok(body) => body,
+            Err(err) => return throw_vizier_error("json search: parse error", err),
+        };
+
+        let results = body
+            .pointer(&self.config.results)
+            .and_then(|results| results.as_array())
+            .ok_or(VizierError(format!(
+                "json search: no result list at {}",
+                self.config.results
+            )))?;
+
+        Ok(results
+            .iter()
+            .filter_map(|result| {
+                Some(SearchResult {
+                    title: text_at(result, &self.config.title).unwrap_or_default(),
+                    url: text_at(result, &self.config.link)?,
+                    description: text_at(result, &self.config.description).unwrap_or_default(),
+                    published: None,
+                    source: None,
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,147 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agents::tools::{
+        VizierTool,
+        search::{brave::BraveSearch, json::JsonSearch, searxng::SearxngSearch},
+    },
+    config::tools::search::SearchBackendConfig,
+    error::VizierError,
+};
+
+mod brave;
+mod json;
+mod searxng;
+
+pub const PAGE_SIZE: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchKind {
+    Web,
+    News,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub query: String,
+    /// starts from 1
+    pub page: u32,
+    pub kind: SearchKind,
+}
+
+/// a search result, the same whichever backend found it
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait SearchProvider: Send + Sync {
+    fn supports(&self, kind: SearchKind) -> bool;
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, VizierError>;
+}
+
+pub fn search_provider(config: &SearchBackendConfig) -> Arc<dyn SearchProvider> {
+    match config {
+        SearchBackendConfig::Brave(config) => Arc::new(BraveSearch::new(config)),
+        SearchBackendConfig::Searxng(config) => Arc::new(SearxngSearch::new(config)),
+        SearchBackendConfig::Json(config) => Arc::new(JsonSearch::new(config)),
+    }
+}
+
+pub trait SearchType {
+    const NAME: &'static str;
+    const KIND: SearchKind;
+    fn description() -> String {
+        "search the general informations on certain topic on the internet".into()
+    }
+}
+
+pub struct WebOnlySearch;
+impl SearchType for WebOnlySearch {
+    const NAME: &'static str = "web_search";
+    const KIND: SearchKind = SearchKind::Web;
+}
+
+pub struct NewsOnlySearch;
+impl SearchType for NewsOnlySearch {
+    const NAME: &'static str = "news_search";
+    const KIND: SearchKind = SearchKind::News;
+
+    fn description() -> String {
+        "find the latest news".into()
+    }
+}
+
+pub struct WebSearch<T: SearchType> {
+    _phantom: PhantomData<T>,
+    provider: Arc<dyn SearchProvider>,
+}
+
+impl<T: SearchType> WebSearch<T> {
+    pub fn new(provider: Arc<dyn SearchProvider>) -> Self {
+        Self {
+            _phantom: PhantomData,
+            provider,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct WebSearchArgs {
+    #[schemars(description = "Terms, keywords, or prompt to search")]
+    pub query: String,
+    #[schemars(description = "page of the search results, starts from 1")]
+    pub page: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema)]
+pub struct WebSearchOutput {
+    pub query: String,
+    pub page: u32,
+    pub results: Vec<SearchResult>,
+}
+
+#[async_trait::async_trait]
+impl<T: SearchType> VizierTool for WebSearch<T>
+where
+    T: Sync + Send,
+{
+    type Input = WebSearchArgs;
+    type Output = WebSearchOutput;
+
+    fn name() -> String {
+        T::NAME.to_string()
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{}, use intervals between the usage of these tools",
+            T::description()
+        )
+    }
+
+    async fn call(&self, args: Self::Input) -> Result<Self::Output, VizierError> {
+        let query = SearchQuery {
+            query: args.query,
+            page: args.page.max(1),
+            kind: T::KIND,
+        };
+        let results = self.provider.search(&query).await?;
+
+        Ok(WebSearchOutput {
+            query: query.query,
+            page: query.page,
+            results,
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Deserialize;
+
+use crate::{
+    agents::tools::search::{SearchKind, SearchProvider, SearchQuery, SearchResult},
+    config::tools::search::SearxngConfig,
+    error::{VizierError, throw_vizier_error},
+};
+
+#[derive(Debug, Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearxngResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+    #[serde(rename = "publishedDate")]
+    published_date: Option<String>,
+    engine: Option<String>,
+}
+
+/// a searxng instance, through its JSON format
+pub struct SearxngSearch {
+    config: SearxngConfig,
+}
+
+impl SearxngSearch {
+    pub fn new(config: &SearxngConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchProvider for SearxngSearch {
+    fn supports(&self, _kind: SearchKind) -> bool {
+        true
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, VizierError> {
+        let mut params = vec![
+            ("q", query.query.clone()),
+            ("format", "json".to_string()),
+            ("pageno", query.page.to_string()),
+            (
+                "categories",
+                match query.kind {
+                    SearchKind::Web => "general",
+                    SearchKind::News => "news",
+                }
+                .to_string(),
+            ),
+            (
+                "safesearch",
+                if self.config.safesearch { "2" } else { "0" }.to_string(),
+            ),
+        ];
+        if !self.config.engines.is_empty() {
+            params.push(("engines", self.config.engines.join(",")));
+        }
+
+        let params =
+            serde_urlencoded::to_string(&params).map_err(|err| VizierError(err.to_string()))?;
+        let url = format!(
+            "{}/search?{}",
+            self.config.url.trim_end_matches('/'),
+            params
+        );
+
+        let response = match reqwest::get(url).await {
+            Ok(response) => response,
+            Err(err) => return throw_vizier_error("searxng: http error", err),
+        };
+        let status = response.status();
+        if !status.is_success() {
+            log::error!("searxng: status error: {}", status);
+            return Err(VizierError(format!("searxng: status error: {}", status)));
+        }
+
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(err) => return throw_vizier_error("searxng: text error", err),
+        };
+        let response = match serde_json::from_str::<SearxngResponse>(&text) {
+            Ok(response) => response,
+            Err(err) => return throw_vizier_error("searxng: parse error", err),
+        };
+
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| SearchResult {
+                title: result.title,
+                url: result.url,
+                description: result.content,
+                published: result.published_date,
+                source: result.engine,
+            })
+            .collect())
+    }
+}
@@ -97,6 +97,10 @@ impl VizierTool for SharedDocumentWrite {
         "shared_document_write".to_string()
     }
 
+    fn exclusive() -> bool {
+        true
+    }
+
     fn description(&self) -> String {
         "Write or update a shared document for all agents to see".into()
     }
@@ -25,6 +25,10 @@ impl VizierTool for ShellExec {
         "shell_exec".to_string()
     }
 
+    fn exclusive() -> bool {
+        true
+    }
+
     fn description(&self) -> String {
         "run a a CLI command on a workspace directory".into()
     }
@@ -70,6 +70,10 @@ where
         T::WRITE_NAME.to_string()
     }
 
+    fn exclusive() -> bool {
+        true
+    }
+
     fn description(&self) -> String {
         format!(
             "write over the content {} file, **not append**. Always tell user after updating document!",
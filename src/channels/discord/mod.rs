@@ -1,26 +1,26 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use chrono::Utc;
-use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serenity::all::{
     ChannelId, Command, CreateCommand, CreateCommandOption, CreateInteractionResponseMessage, Http,
-    Interaction, Ready, Typing,
+    Interaction, MessageId, Ready, Typing,
 };
 use serenity::async_trait;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
 
+use crate::agents::command::VizierCommands;
 use crate::channels::VizierChannel;
 use crate::config::DiscordChannelConfig;
 use crate::dependencies::VizierDependencies;
 use crate::schema::{
-    TopicId, VizierAttachment, VizierAttachmentContent, VizierChannelId, VizierRequest,
+    VizierAttachment, VizierAttachmentContent, VizierChannelId, VizierRequest,
     VizierRequestContent, VizierResponse, VizierResponseContent, VizierSession,
 };
-use crate::storage::session::SessionStorage;
 use crate::storage::state::StateStorage;
 use crate::transport::VizierTransport;
 use crate::utils::remove_think_tags;
@@ -54,6 +54,15 @@ impl VizierChannel for DiscordChannelReader {
     }
 }
 
+// discord rate limits message edits, so deltas are buffered between edits
+const STREAM_EDIT_INTERVAL: Duration = Duration::from_secs(1);
+
+struct StreamState {
+    message_id: Option<MessageId>,
+    content: String,
+    last_edit: Instant,
+}
+
 pub struct DiscordChannelWriter {
     transport: VizierTransport,
     config: HashMap<String, DiscordChannelConfig>,
@@ -76,6 +85,9 @@ impl VizierChannel for DiscordChannelWriter {
         let mut recv = self.transport.subscribe_response().await?;
         let _ = tokio::spawn(async move {
             let mut typing_state = HashMap::<u64, Typing>::new();
+            let mut stream_state = HashMap::<u64, StreamState>::new();
+            // approval id -> the request message and its content
+            let mut approval_requests = HashMap::<String, (MessageId, String)>::new();
             loop {
                 if let Ok((
                     VizierSession(agent_id, VizierChannelId::DiscordChanel(channel_id), _),
@@ -96,11 +108,58 @@ impl VizierChannel for DiscordChannelWriter {
                                 Typing::start(http.clone(), discord_channel_id),
                             );
                         }
+                        VizierResponse {
+                            content: VizierResponseContent::MessageDelta(delta),
+                            timestamp: _,
+                            attachments: _,
+                        } => {
+                            let state = stream_state.entry(channel_id).or_insert(StreamState {
+                                message_id: None,
+                                content: String::new(),
+                                last_edit: Instant::now(),
+                            });
+                            state.content.push_str(&delta);
+
+                            if state.content.trim().is_empty()
+                                || (state.message_id.is_some()
+                                    && state.last_edit.elapsed() < STREAM_EDIT_INTERVAL)
+                            {
+                                continue;
+                            }
+
+                            if let Ok(message_id) = crate::utils::discord::stream_message(
+                                http.clone(),
+                                &discord_channel_id,
+                                state.message_id,
+                                &remove_think_tags(&state.content),
+                            )
+                            .await
+                            {
+                                state.message_id = Some(message_id);
+                                state.last_edit = Instant::now();
+                            }
+                        }
                         VizierResponse {
                             content: VizierResponseContent::ToolChoice { name, args },
                             timestamp: _,
                             attachments: _,
                         } => {
+                            // text streamed before a tool call stays as its own message
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                content,
+                                ..
+                            }) = stream_state.remove(&channel_id)
+                            {
+                                let _ = crate::utils::discord::finalize_stream_message(
+                                    http.clone(),
+                                    &discord_channel_id,
+                                    message_id,
+                                    remove_think_tags(&content),
+                                )
+                                .await;
+                            }
+
                             let _ = crate::utils::discord::send_message(
                                 http.clone(),
                                 &discord_channel_id,
@@ -108,11 +167,83 @@ impl VizierChannel for DiscordChannelWriter {
                             )
                             .await;
                         }
+                        VizierResponse {
+                            content: VizierResponseContent::ApprovalRequest { id, name, args },
+                            timestamp: _,
+                            attachments: _,
+                        } => {
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                content,
+                                ..
+                            }) = stream_state.remove(&channel_id)
+                            {
+                                let _ = crate::utils::discord::finalize_stream_message(
+                                    http.clone(),
+                                    &discord_channel_id,
+                                    message_id,
+                                    remove_think_tags(&content),
+                                )
+                                .await;
+                            }
+
+                            let content = crate::utils::format_approval_request(&name, &args);
+                            match crate::utils::discord::send_approval_request(
+                                http.clone(),
+                                &discord_channel_id,
+                                &id,
+                                content.clone(),
+                            )
+                            .await
+                            {
+                                Ok(message_id) => {
+                                    approval_requests.insert(id, (message_id, content));
+                                }
+                                Err(err) => log::error!("{}", err),
+                            }
+                        }
+                        VizierResponse {
+                            content: VizierResponseContent::ApprovalResolved { id, approved, by },
+                            timestamp: _,
+                            attachments: _,
+                        } => {
+                            let Some((message_id, content)) = approval_requests.remove(&id) else {
+                                continue;
+                            };
+
+                            if let Err(err) = crate::utils::discord::resolve_approval_request(
+                                http.clone(),
+                                &discord_channel_id,
+                                message_id,
+                                content,
+                                approved,
+                                &by,
+                            )
+                            .await
+                            {
+                                log::error!("{}", err);
+                            }
+                        }
                         VizierResponse {
                             content: VizierResponseContent::Thinking(thought),
                             timestamp: _,
                             attachments: _,
                         } => {
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                content,
+                                ..
+                            }) = stream_state.remove(&channel_id)
+                            {
+                                let _ = crate::utils::discord::finalize_stream_message(
+                                    http.clone(),
+                                    &discord_channel_id,
+                                    message_id,
+                                    remove_think_tags(&content),
+                                )
+                                .await;
+                            }
+
                             let _ = crate::utils::discord::send_message(
                                 http.clone(),
                                 &discord_channel_id,
@@ -123,19 +254,42 @@ impl VizierChannel for DiscordChannelWriter {
                         VizierResponse {
                             content: VizierResponseContent::Message { content, stats: _ },
                             timestamp: _,
-                            attachments: _,
+                            attachments,
                         } => {
                             if let Some(typing) = typing_state.remove(&channel_id) {
                                 typing.stop();
                             }
 
                             let content = remove_think_tags(&content.clone());
-                            let _ = crate::utils::discord::send_message(
-                                http.clone(),
-                                &discord_channel_id,
-                                content,
-                            )
-                            .await;
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                ..
+                            }) = stream_state.remove(&channel_id)
+                            {
+                                let _ = crate::utils::discord::finalize_stream_message(
+                                    http.clone(),
+                                    &discord_channel_id,
+                                    message_id,
+                                    content,
+                                )
+                                .await;
+                            } else {
+                                let _ = crate::utils::discord::send_message(
+                                    http.clone(),
+                                    &discord_channel_id,
+                                    content,
+                                )
+                                .await;
+                            }
+
+                            if !attachments.is_empty() {
+                                let _ = crate::utils::discord::send_attachments(
+                                    http.clone(),
+                                    &discord_channel_id,
+                                    attachments,
+                                )
+                                .await;
+                            }
                         }
                         VizierResponse {
                             content: VizierResponseContent::Abort,
@@ -145,6 +299,7 @@ impl VizierChannel for DiscordChannelWriter {
                             if let Some(typing) = typing_state.remove(&channel_id) {
                                 typing.stop();
                             }
+                            stream_state.remove(&channel_id);
 
                             let _ = crate::utils::discord::send_message(
                                 http.clone(),
@@ -167,184 +322,124 @@ impl VizierChannel for DiscordChannelWriter {
 
 struct Handler(String, VizierDependencies);
 
-#[derive(Debug, Deserialize, Serialize)]
-struct ChannelState {
-    active_topic: Option<TopicId>,
-}
-
 #[async_trait]
 impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, _ready: Ready) {
-        let ping = CreateCommand::new("ping").description("a simple ping");
-        // let help = CreateCommand::new("help").description("How to use me");
-
-        let new = CreateCommand::new("new").description("create fresh new session");
-        let session = CreateCommand::new("session")
-            .description("list or select session")
-            .add_option(CreateCommandOption::new(
-                serenity::all::CommandOptionType::String,
-                "topic_id",
-                "switch to the topic if not empty",
-            ));
-
-        let _ = Command::create_global_command(ctx.http.clone(), ping).await;
-        // let _ = Command::create_global_command(ctx.http.clone(), help).await;
-
-        let _ = Command::create_global_command(ctx.http.clone(), new).await;
-        let _ = Command::create_global_command(ctx.http.clone(), session).await;
+        for command in VizierCommands::new().commands() {
+            let mut create = CreateCommand::new(command.name()).description(command.description());
+            if !command.usage().is_empty() {
+                create = create.add_option(CreateCommandOption::new(
+                    serenity::all::CommandOptionType::String,
+                    "args",
+                    command.usage(),
+                ));
+            }
+
+            let _ = Command::create_global_command(ctx.http.clone(), create).await;
+        }
+
+        let help = CreateCommand::new("help").description("list available commands");
+        let _ = Command::create_global_command(ctx.http.clone(), help).await;
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::Command(command) = interaction {
-            let agent_id = self.0.clone();
+        let (channel_id, user, raw) = match interaction {
+            Interaction::Command(command) => {
+                let args = command
+                    .data
+                    .options
+                    .iter()
+                    .find_map(|opt| {
+                        if opt.name == "args" {
+                            opt.value.as_str().map(|value| value.to_string())
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap_or_default();
+                let raw = format!("/{} {}", command.data.name, args)
+                    .trim()
+                    .to_string();
 
-            if command.data.name == "ping" {
-                let _ = command
+                // the output is delivered by the writer like any other response
+                if let Err(err) = command
                     .create_response(
                         ctx.http.clone(),
                         serenity::all::CreateInteractionResponse::Message(
-                            CreateInteractionResponseMessage::new().content("Pong!"),
+                            CreateInteractionResponseMessage::new().content(format!("`{}`", raw)),
                         ),
                     )
-                    .await;
-            }
-
-            if command.data.name == "new" {
-                let channel = VizierChannelId::DiscordChanel(command.channel_id.get());
-                let topic_id = nanoid::nanoid!(10);
-
-                let _ = self
-                    .1
-                    .storage
-                    .save_state(
-                        format!("{}__{}", agent_id, channel.to_slug()),
-                        serde_json::to_value(ChannelState {
-                            active_topic: Some(topic_id.clone()),
-                        })
-                        .unwrap(),
-                    )
-                    .await;
+                    .await
+                {
+                    log::error!("{}", err)
+                }
 
-                let _ = command
-                    .create_response(
-                        ctx.http.clone(),
-                        serenity::all::CreateInteractionResponse::Message(
-                            CreateInteractionResponseMessage::new()
-                                .content(format!("switch to new session: **{}**", topic_id)),
-                        ),
-                    )
-                    .await;
+                (command.channel_id, command.user, raw)
             }
-
-            if command.data.name == "session" {
-                let channel = VizierChannelId::DiscordChanel(command.channel_id.get());
-                let opt = command.data.options.clone();
-
-                if let Some(raw_topic_id) = opt.iter().find_map(|opt| {
-                    if opt.name == "topic_id".to_string() {
-                        Some(opt.value.as_str().unwrap().to_string())
-                    } else {
-                        None
-                    }
-                }) {
-                    let topic_id: Option<TopicId> = if raw_topic_id == "DEFAULT".to_string() {
-                        None
-                    } else {
-                        Some(raw_topic_id.clone())
-                    };
-
-                    if let Ok(Some(_)) = self
-                        .1
-                        .storage
-                        .get_session_detail_by_topic(
-                            agent_id.clone(),
-                            channel.clone(),
-                            topic_id.clone(),
-                        )
-                        .await
-                    {
-                        let _ = self
-                            .1
-                            .storage
-                            .save_state(
-                                format!("{}__{}", agent_id, channel.to_slug()),
-                                serde_json::to_value(ChannelState {
-                                    active_topic: topic_id,
-                                })
-                                .unwrap(),
-                            )
-                            .await;
-
-                        let _ = command
-                            .create_response(
-                                ctx.http.clone(),
-                                serenity::all::CreateInteractionResponse::Message(
-                                    CreateInteractionResponseMessage::new().content(format!(
-                                        "switch to session: **{}**",
-                                        raw_topic_id
-                                    )),
-                                ),
-                            )
-                            .await;
-                    } else {
-                        let _ = command
-                            .create_response(
-                                ctx.http.clone(),
-                                serenity::all::CreateInteractionResponse::Message(
-                                    CreateInteractionResponseMessage::new()
-                                        .content("topic not found"),
-                                ),
-                            )
-                            .await;
-                    }
-                } else {
-                    if let Ok(sessions) = self
-                        .1
-                        .storage
-                        .get_session_list(agent_id, Some(channel))
-                        .await
-                    {
-                        let mut res = vec![];
-                        for session in &sessions {
-                            res.push(format!(
-                                "topic_id: {}\ntitle: {}",
-                                session.topic.clone().unwrap_or("DEFAULT".into()),
-                                session.title.clone()
-                            ));
-                        }
-
-                        let output = res.join("\n\n");
-                        let _ = command
-                            .create_response(
-                                ctx.http.clone(),
-                                serenity::all::CreateInteractionResponse::Message(
-                                    CreateInteractionResponseMessage::new().content(output),
-                                ),
-                            )
-                            .await;
-                    }
+            // approval buttons, `approve:<id>` or `deny:<id>`
+            Interaction::Component(component) => {
+                let Some((action, approval_id)) = component.data.custom_id.split_once(':') else {
+                    return;
+                };
+                if action != "approve" && action != "deny" {
+                    return;
                 }
-            }
 
-            if command.data.name == "help" {
-                if let Err(err) = command
+                // the buttons go once the agent accepts the answer, someone
+                // who may not answer leaves them in place
+                if let Err(err) = component
                     .create_response(
                         ctx.http.clone(),
-                        serenity::all::CreateInteractionResponse::Message(
-                            CreateInteractionResponseMessage::new().content(
-                                r#"
-Just mention me when you need to summon me.
-I will only read the chat otherwise.
-If I am halucinating, feel free to `/lobotomy` me
-                            "#,
-                            ),
-                        ),
+                        serenity::all::CreateInteractionResponse::Acknowledge,
                     )
                     .await
                 {
                     log::error!("{}", err)
                 }
+
+                (
+                    component.channel_id,
+                    component.user,
+                    format!("/{} {}", action, approval_id),
+                )
             }
+            _ => return,
+        };
+
+        let agent_id = self.0.clone();
+        let channel = VizierChannelId::DiscordChanel(channel_id.get());
+        let topic_id = self
+            .1
+            .storage
+            .get_active_topic(agent_id.clone(), channel.clone())
+            .await
+            .unwrap_or(None);
+
+        if let Err(err) = self
+            .1
+            .transport
+            .send_request(
+                VizierSession(agent_id, channel, topic_id),
+                VizierRequest {
+                    timestamp: chrono::Utc::now(),
+                    user: format!(
+                        "@{} (DiscordId: {})",
+                        user.display_name(),
+                        user.id.to_string()
+                    ),
+                    content: VizierRequestContent::Command(raw),
+                    metadata: json!({
+                        "sent_at": Utc::now().to_string(),
+                        "discord_channel_id": channel_id.to_string(),
+                        "discord_user_id": user.id.to_string(),
+                    }),
+
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            log::error!("{}", err)
         }
     }
 
@@ -352,14 +447,12 @@ If I am halucinating, feel free to `/lobotomy` me
         let agent_id = self.0.clone();
         let channel = VizierChannelId::DiscordChanel(msg.channel_id.get());
 
-        let key = format!("{}__{}", agent_id, channel.to_slug());
-        let topic_id = if let Ok(Some(value)) = self.1.storage.get_state(key).await {
-            let state = serde_json::from_value::<ChannelState>(value).unwrap();
-
-            state.active_topic
-        } else {
-            None
-        };
+        let topic_id = self
+            .1
+            .storage
+            .get_active_topic(agent_id.clone(), channel.clone())
+            .await
+            .unwrap_or(None);
 
         let is_dm = msg.guild_id.is_none();
 
@@ -390,6 +483,7 @@ If I am halucinating, feel free to `/lobotomy` me
                 "replied_message_id": replied_to,
                 "message_id": msg.id.to_string(),
                 "discord_channel_id": msg.channel_id.to_string(),
+                "discord_user_id": msg.author.id.to_string(),
                 "is_dm": is_dm,
             });
 
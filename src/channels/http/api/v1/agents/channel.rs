@@ -6,6 +6,7 @@ use axum::{
     },
     routing::{any, delete, get},
 };
+use base64::Engine;
 use chrono::Utc;
 use futures::{SinkExt, StreamExt};
 use reqwest::StatusCode;
@@ -19,14 +20,18 @@ use crate::{
         },
         state::HTTPState,
     },
+    error::VizierError,
     schema::{
         SessionHistory, TopicId, VizierAttachmentContent, VizierChannelId, VizierRequest,
-        VizierSession, VizierSessionDetail,
+        VizierResponse, VizierResponseContent, VizierSession, VizierSessionDetail,
     },
     storage::{history::HistoryStorage, session::SessionStorage},
     transport::VizierTransport,
+    utils::{egress::EgressPolicy, split_oversized_attachments},
 };
 
+const MAX_ATTACHMENT_SIZE: usize = 25 * 1024 * 1024;
+
 pub fn channel() -> Router<HTTPState> {
     Router::new()
         .route("/{channel_id}/topics", get(list_topics))
@@ -205,15 +210,17 @@ pub async fn chat(
     }
 
     let transport = state.transport.clone();
+    let egress = EgressPolicy::new(&state.config.agents[&agent_id].tools.egress);
     let session = VizierSession(agent_id, VizierChannelId::HTTP(channel_id), Some(topic_id));
 
-    ws.on_upgrade(move |socket| handle_socket(socket, session, transport))
+    ws.on_upgrade(move |socket| handle_socket(socket, session, transport, egress))
 }
 
 pub async fn handle_socket(
     socket: WebSocket,
     curr_session: VizierSession,
     transport: VizierTransport,
+    egress: EgressPolicy,
 ) {
     let (mut writer, mut reader) = socket.split();
 
@@ -224,6 +231,7 @@ pub async fn handle_socket(
             if session != req_session {
                 continue;
             }
+            let response = with_deliverable_attachments(response);
             let _ = writer
                 .send(axum::extract::ws::Message::Text(
                     serde_json::to_string(&response).unwrap().into(),
@@ -238,18 +246,24 @@ pub async fn handle_socket(
                 Message::Text(text) => {
                     if let Ok(request) = serde_json::from_str::<VizierRequest>(&text.to_string()) {
                         let mut request = request.clone();
-                        for attachment in request.attachments.iter_mut() {
+                        let mut attachments = vec![];
+                        for mut attachment in request.attachments {
                             if let VizierAttachmentContent::Url(url) = &attachment.content {
-                                if let Ok(response) = reqwest::get(url).await {
-                                    if response.status().is_success() {
-                                        if let Ok(bytes) = response.bytes().await {
-                                            attachment.content =
-                                                VizierAttachmentContent::Bytes(bytes.to_vec());
-                                        }
+                                match download_attachment(&egress, url).await {
+                                    Ok(Some(bytes)) => {
+                                        attachment.content = VizierAttachmentContent::Bytes(bytes)
+                                    }
+                                    Ok(None) => {}
+                                    // urls outside the egress policy are dropped
+                                    Err(err) => {
+                                        log::warn!("attachment {} dropped: {}", url, err);
+                                        continue;
                                     }
                                 }
                             }
+                            attachments.push(attachment);
                         }
+                        request.attachments = attachments;
 
                         let _ = transport.send_request(curr_session.clone(), request).await;
                     }
@@ -262,3 +276,49 @@ pub async fn handle_socket(
 
     handle.abort();
 }
+
+/// downloads a url attachment within the egress policy, `None` when the
+/// server doesn't answer with the file
+async fn download_attachment(
+    egress: &EgressPolicy,
+    url: &str,
+) -> Result<Option<Vec<u8>>, VizierError> {
+    let client = egress.client_for(url, None)?;
+    let Ok(response) = client.get(url).send().await else {
+        return Ok(None);
+    };
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    egress.read_body(response).await.map(Some)
+}
+
+/// raw bytes are sent as base64, files above the limit are replaced by a note
+fn with_deliverable_attachments(response: VizierResponse) -> VizierResponse {
+    if response.attachments.is_empty() {
+        return response;
+    }
+
+    let mut response = response;
+    let (mut attachments, note) = split_oversized_attachments(
+        std::mem::take(&mut response.attachments),
+        MAX_ATTACHMENT_SIZE,
+    );
+    for attachment in attachments.iter_mut() {
+        if let VizierAttachmentContent::Bytes(bytes) = &attachment.content {
+            attachment.content = VizierAttachmentContent::Base64(
+                base64::engine::general_purpose::STANDARD.encode(bytes),
+            );
+        }
+    }
+    response.attachments = attachments;
+
+    if let (Some(note), VizierResponseContent::Message { content, .. }) =
+        (note, &mut response.content)
+    {
+        content.push_str(&format!("\n\n{}", note));
+    }
+
+    response
+}
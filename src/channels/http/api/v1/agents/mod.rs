@@ -23,11 +23,13 @@ use crate::{
 pub mod channel;
 pub mod documents;
 pub mod memory;
+pub mod structured;
 pub mod task;
 
 use channel::channel;
 use documents::documents;
 use memory::memory;
+use structured::structured;
 use task::task;
 
 impl VizierConfig {
@@ -44,6 +46,7 @@ pub fn agents() -> Router<HTTPState> {
         .nest("/{agent_id}/channel", channel())
         .nest("/{agent_id}/documents", documents())
         .nest("/{agent_id}/memory", memory())
+        .nest("/{agent_id}/structured", structured())
         .nest("/{agent_id}/tasks", task())
 }
 
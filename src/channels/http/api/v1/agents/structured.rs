@@ -0,0 +1,167 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::post,
+};
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agents::agent::SCHEMA_MISMATCH,
+    channels::http::{
+        models::{
+            self,
+            response::{APIResponse, api_response, err_response},
+        },
+        state::HTTPState,
+    },
+    schema::{
+        TopicId, VizierChannelId, VizierRequest, VizierRequestContent, VizierResponse,
+        VizierResponseContent, VizierResponseStats, VizierSession,
+    },
+};
+
+// sessions of this endpoint live in their own http channel
+const STRUCTURED_CHANNEL_ID: &str = "structured";
+
+pub fn structured() -> Router<HTTPState> {
+    Router::new().route("/", post(structured_prompt))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct StructuredRequest {
+    prompt: String,
+    /// JSON Schema of the expected answer
+    #[schema(value_type = Object)]
+    schema: serde_json::Value,
+    /// continue an earlier conversation, a new topic is used when empty
+    topic_id: Option<TopicId>,
+    user: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, utoipa::ToSchema)]
+pub struct StructuredResponse {
+    topic_id: TopicId,
+    /// answer of the agent, already validated against the schema
+    #[schema(value_type = Object)]
+    output: serde_json::Value,
+    stats: Option<VizierResponseStats>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/agents/{agent_id}/structured",
+    params(
+        ("agent_id" = String, Path, description = "Agent ID")
+    ),
+    request_body = StructuredRequest,
+    responses(
+        (status = 200, description = "Answer matching the schema", body = APIResponse<StructuredResponse>),
+        (status = 404, description = "Agent not found", body = APIResponse<String>),
+        (status = 409, description = "Interrupted by another request on the topic", body = APIResponse<String>),
+        (status = 422, description = "No answer matching the schema", body = APIResponse<String>),
+        (status = 502, description = "The agent failed to answer", body = APIResponse<String>),
+        (status = 504, description = "Agent did not answer in time", body = APIResponse<String>)
+    )
+)]
+pub async fn structured_prompt(
+    Path(agent_id): Path<String>,
+    State(state): State<HTTPState>,
+    Json(req): Json<StructuredRequest>,
+) -> models::response::Response<StructuredResponse> {
+    let Some(agent_config) = state.config.agents.get(&agent_id) else {
+        return err_response(StatusCode::NOT_FOUND, format!("agent {agent_id} not found"));
+    };
+
+    if let Err(err) = jsonschema::validator_for(&req.schema) {
+        return err_response(StatusCode::BAD_REQUEST, format!("invalid schema: {}", err));
+    }
+
+    let topic_id = req.topic_id.unwrap_or_else(|| nanoid::nanoid!(10));
+    let session = VizierSession(
+        agent_id,
+        VizierChannelId::HTTP(STRUCTURED_CHANNEL_ID.into()),
+        Some(topic_id.clone()),
+    );
+
+    // subscribe before sending so the answer can't be missed
+    let mut recv = match state.transport.subscribe_response().await {
+        Ok(recv) => recv,
+        Err(err) => return err_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+
+    if let Err(err) = state
+        .transport
+        .send_request(
+            session.clone(),
+            VizierRequest {
+                timestamp: Utc::now(),
+                user: req.user.unwrap_or("api".into()),
+                content: VizierRequestContent::Chat(req.prompt),
+                metadata: serde_json::json!({
+                    "sent_at": Utc::now().to_string(),
+                }),
+                response_schema: Some(req.schema),
+
+                ..Default::default()
+            },
+        )
+        .await
+    {
+        return err_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string());
+    }
+
+    // a mismatching answer is retried a couple of times by the agent
+    let wait = *agent_config.prompt_timeout * 3;
+    let answer = tokio::time::timeout(wait, async {
+        while let Ok((res_session, response)) = recv.recv().await {
+            if res_session != session {
+                continue;
+            }
+
+            match response {
+                VizierResponse {
+                    content: VizierResponseContent::Message { content, stats },
+                    ..
+                } => return Some(Ok((content, stats))),
+                VizierResponse {
+                    content: VizierResponseContent::Abort,
+                    ..
+                } => return Some(Err(())),
+                _ => {}
+            }
+        }
+
+        None
+    })
+    .await;
+
+    match answer {
+        Ok(Some(Ok((content, stats)))) => match serde_json::from_str(&content) {
+            Ok(output) => api_response(
+                StatusCode::OK,
+                StructuredResponse {
+                    topic_id,
+                    output,
+                    stats,
+                },
+            ),
+            // errors of the turn come back as plain messages, only an answer
+            // that never matched the schema is the client's to fix
+            Err(_) if content.contains(SCHEMA_MISMATCH) => {
+                err_response(StatusCode::UNPROCESSABLE_ENTITY, content)
+            }
+            Err(_) => err_response(StatusCode::BAD_GATEWAY, content),
+        },
+        Ok(Some(Err(()))) => err_response(
+            StatusCode::CONFLICT,
+            "interrupted by another request".into(),
+        ),
+        Ok(None) => err_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "response channel closed".into(),
+        ),
+        Err(_) => err_response(StatusCode::GATEWAY_TIMEOUT, "agent timed out".into()),
+    }
+}
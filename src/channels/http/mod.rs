@@ -104,6 +104,7 @@ impl VizierChannel for HTTPChannel {
         api::v1::agents::memory::update_memory,
         api::v1::agents::memory::delete_memory,
         api::v1::agents::memory::query_memories,
+        api::v1::agents::structured::structured_prompt,
         api::v1::agents::task::get_tasks,
         api::v1::agents::task::get_task,
         api::v1::agents::task::create_task,
@@ -134,6 +135,8 @@ impl VizierChannel for HTTPChannel {
             api::v1::agents::memory::MemoryDetail,
             api::v1::agents::memory::CreateMemoryResponse,
             api::v1::agents::memory::UpdateMemoryResponse,
+            api::v1::agents::structured::StructuredRequest,
+            api::v1::agents::structured::StructuredResponse,
             api::v1::agents::task::GetTasksQuery,
             api::v1::agents::task::CreateTaskRequest,
             api::v1::agents::task::ScheduleRequest,
@@ -152,6 +155,7 @@ impl VizierChannel for HTTPChannel {
             crate::channels::http::models::response::APIResponse<Vec<api::v1::agents::memory::MemoryDetail>>,
             crate::channels::http::models::response::APIResponse<api::v1::agents::memory::CreateMemoryResponse>,
             crate::channels::http::models::response::APIResponse<api::v1::agents::memory::UpdateMemoryResponse>,
+            crate::channels::http::models::response::APIResponse<api::v1::agents::structured::StructuredResponse>,
             crate::channels::http::models::response::APIResponse<api::v1::agents::task::TaskResponse>,
             crate::channels::http::models::response::APIResponse<Vec<api::v1::agents::task::TaskResponse>>,
             crate::channels::http::models::response::APIResponse<api::v1::files::UploadResponse>,
@@ -14,6 +14,7 @@ pub mod discord;
 pub mod http;
 pub mod telegram;
 
+#[allow(async_fn_in_trait)]
 pub trait VizierChannel {
     async fn run(&mut self) -> Result<()>;
 }
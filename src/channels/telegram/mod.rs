@@ -1,20 +1,20 @@
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use chrono::Utc;
-use serde::{Deserialize, Serialize};
 use teloxide::Bot;
 use teloxide::prelude::*;
-use teloxide::types::ChatAction;
+use teloxide::types::{BotCommand, CallbackQuery, ChatAction, MessageId};
 
+use crate::agents::command::VizierCommands;
 use crate::channels::VizierChannel;
 use crate::config::TelegramChannelConfig;
 use crate::dependencies::VizierDependencies;
 use crate::schema::{
-    TopicId, VizierAttachment, VizierAttachmentContent, VizierChannelId, VizierRequest,
+    VizierAttachment, VizierAttachmentContent, VizierChannelId, VizierRequest,
     VizierRequestContent, VizierResponse, VizierResponseContent, VizierSession,
 };
-use crate::storage::session::SessionStorage;
 use crate::storage::state::StateStorage;
 use crate::transport::VizierTransport;
 use crate::utils::remove_think_tags;
@@ -24,6 +24,7 @@ pub struct TelegramChannelReader {
     token: String,
     agent_id: String,
     deps: VizierDependencies,
+    commands: VizierCommands,
     offset: i64,
 }
 
@@ -35,12 +36,23 @@ impl TelegramChannelReader {
     ) -> Result<Self> {
         let bot = Bot::new(config.token.clone());
         let token = config.token;
+        let commands = VizierCommands::new();
+
+        let mut bot_commands = commands
+            .commands()
+            .map(|command| BotCommand::new(command.name(), command.description()))
+            .collect::<Vec<_>>();
+        bot_commands.push(BotCommand::new("help", "list available commands"));
+        if let Err(err) = bot.set_my_commands(bot_commands).await {
+            log::warn!("failed to register telegram commands: {}", err);
+        }
 
         Ok(Self {
             bot,
             token,
             agent_id,
             deps,
+            commands,
             offset: 0,
         })
     }
@@ -80,6 +92,9 @@ impl TelegramChannelReader {
         let msg = match kind {
             teloxide::types::UpdateKind::Message(msg) => msg.clone(),
             teloxide::types::UpdateKind::EditedMessage(msg) => msg.clone(),
+            teloxide::types::UpdateKind::CallbackQuery(query) => {
+                return self.handle_callback_query(query).await;
+            }
             _ => return Ok(()),
         };
 
@@ -88,13 +103,12 @@ impl TelegramChannelReader {
 
         let is_dm = msg.chat.is_private();
 
-        let key = format!("{}__{}", self.agent_id, channel.to_slug());
-        let topic_id = if let Ok(Some(value)) = self.deps.storage.get_state(key).await {
-            let state = serde_json::from_value::<ChannelState>(value).unwrap();
-            state.active_topic
-        } else {
-            None
-        };
+        let topic_id = self
+            .deps
+            .storage
+            .get_active_topic(self.agent_id.clone(), channel.clone())
+            .await
+            .unwrap_or(None);
 
         let text = msg.text().unwrap_or("").to_string();
         let bot_username = self.bot.get_me().await?.username().to_string();
@@ -107,8 +121,9 @@ impl TelegramChannelReader {
         let user_full_name = msg
             .from
             .as_ref()
-            .map(|u| u.username.clone().unwrap())
+            .map(|u| u.username.clone().unwrap_or_else(|| u.full_name()))
             .unwrap_or_else(|| "Unknown".into());
+        let user_id = msg.from.as_ref().map(|u| u.id.0.to_string());
 
         let metadata = serde_json::json!({
             "sent_at": Utc::now().to_string(),
@@ -116,6 +131,8 @@ impl TelegramChannelReader {
             "replied_message_id": replied_to,
             "message_id": message_id,
             "telegram_chat_id": chat_id.to_string(),
+            "telegram_username": user_full_name,
+            "telegram_user_id": user_id,
             "is_dm": is_dm,
         });
 
@@ -159,93 +176,27 @@ impl TelegramChannelReader {
 
         let transport = self.deps.transport.clone();
 
-        if text.starts_with("/ping") {
-            let _ = self.bot.send_message(chat_id, "Pong!").await?;
-            return Ok(());
-        }
-
-        if text.starts_with("/new") {
-            let topic_id = nanoid::nanoid!(10);
-            let _ = self
-                .deps
-                .storage
-                .save_state(
-                    format!("{}__{}", self.agent_id, channel.to_slug()),
-                    serde_json::to_value(ChannelState {
-                        active_topic: Some(topic_id.clone()),
-                    })
-                    .unwrap(),
-                )
-                .await;
-
-            let _ = self
-                .bot
-                .clone()
-                .parse_mode(teloxide::types::ParseMode::MarkdownV2)
-                .send_message(chat_id, format!("switch to new session: **{}**", topic_id))
-                .await?;
-            return Ok(());
-        }
-
-        if text.starts_with("/session") {
-            let parts: Vec<&str> = text.split_whitespace().collect();
-            if parts.len() > 1 {
-                let raw_topic_id = parts[1];
-                let topic_id: Option<TopicId> = if raw_topic_id == "DEFAULT" {
-                    None
-                } else {
-                    Some(raw_topic_id.to_string())
-                };
-
-                if let Ok(Some(_)) = self
-                    .deps
-                    .storage
-                    .get_session_detail_by_topic(
-                        self.agent_id.clone(),
-                        channel.clone(),
-                        topic_id.clone(),
+        if text.starts_with('/') && self.commands.is_command(&text) {
+            let transport = transport.clone();
+            let agent_id = self.agent_id.clone();
+            tokio::spawn(async move {
+                if let Err(err) = transport
+                    .send_request(
+                        VizierSession(agent_id, channel, topic_id),
+                        VizierRequest {
+                            timestamp: chrono::Utc::now(),
+                            user,
+                            content: VizierRequestContent::Command(text),
+                            metadata,
+                            attachments,
+                            response_schema: None,
+                        },
                     )
                     .await
                 {
-                    let _ = self
-                        .deps
-                        .storage
-                        .save_state(
-                            format!("{}__{}", self.agent_id, channel.to_slug()),
-                            serde_json::to_value(ChannelState {
-                                active_topic: topic_id,
-                            })
-                            .unwrap(),
-                        )
-                        .await;
-
-                    let _ = self
-                        .bot
-                        .send_message(chat_id, format!("switch to session: **{}**", raw_topic_id))
-                        .await?;
-                } else {
-                    let _ = self.bot.send_message(chat_id, "topic not found").await?;
-                }
-            } else {
-                if let Ok(sessions) = self
-                    .deps
-                    .storage
-                    .get_session_list(self.agent_id.clone(), Some(channel.clone()))
-                    .await
-                {
-                    let mut res = vec![];
-                    for session in &sessions {
-                        res.push(format!(
-                            "topic_id: {}\ntitle: {}",
-                            session.topic.clone().unwrap_or("DEFAULT".into()),
-                            session.title.clone()
-                        ));
-                    }
-
-                    let output = res.join("\n\n");
-                    let _ = self.bot.send_message(chat_id, output).await?;
+                    log::error!("{}", err);
                 }
-            }
+            });
             return Ok(());
         }
 
@@ -276,6 +227,7 @@ impl TelegramChannelReader {
                             content: VizierRequestContent::Chat(content),
                             metadata,
                             attachments,
+                            response_schema: None,
                         },
                     )
                     .await
@@ -298,6 +250,7 @@ impl TelegramChannelReader {
                             content: VizierRequestContent::SilentRead(text),
                             metadata,
                             attachments,
+                            response_schema: None,
                         },
                     )
                     .await
@@ -309,6 +262,68 @@ impl TelegramChannelReader {
 
         Ok(())
     }
+
+    /// approval buttons, `approve:<id>` or `deny:<id>`
+    async fn handle_callback_query(&self, query: &CallbackQuery) -> Result<()> {
+        let _ = self.bot.answer_callback_query(query.id.clone()).await;
+
+        let Some((action, approval_id)) = query.data.as_ref().and_then(|data| data.split_once(':'))
+        else {
+            return Ok(());
+        };
+        if action != "approve" && action != "deny" {
+            return Ok(());
+        }
+        let Some(message) = &query.message else {
+            return Ok(());
+        };
+
+        let chat_id = message.chat().id;
+
+        let channel = VizierChannelId::TelegramChannel(chat_id.0);
+        let topic_id = self
+            .deps
+            .storage
+            .get_active_topic(self.agent_id.clone(), channel.clone())
+            .await
+            .unwrap_or(None);
+        let username = query
+            .from
+            .username
+            .clone()
+            .unwrap_or_else(|| "Unknown".into());
+
+        self.deps
+            .transport
+            .send_request(
+                VizierSession(self.agent_id.clone(), channel, topic_id),
+                VizierRequest {
+                    timestamp: chrono::Utc::now(),
+                    user: format!("@{} (TelegramUser: {})", username, chat_id.0),
+                    content: VizierRequestContent::Command(format!("/{} {}", action, approval_id)),
+                    metadata: serde_json::json!({
+                        "sent_at": Utc::now().to_string(),
+                        "telegram_chat_id": chat_id.to_string(),
+                        "telegram_username": username,
+                        "telegram_user_id": query.from.id.0.to_string(),
+                    }),
+                    attachments: vec![],
+                    response_schema: None,
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+// telegram rate limits message edits, so deltas are buffered between edits
+const STREAM_EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+
+struct StreamState {
+    message_id: Option<MessageId>,
+    content: String,
+    last_edit: Instant,
 }
 
 pub struct TelegramChannelWriter {
@@ -333,6 +348,9 @@ impl VizierChannel for TelegramChannelWriter {
         let bots = self.bots.clone();
 
         let mut typing_handles: HashMap<i64, tokio::task::JoinHandle<()>> = HashMap::new();
+        let mut stream_state: HashMap<i64, StreamState> = HashMap::new();
+        // approval id -> the request message
+        let mut approval_requests: HashMap<String, MessageId> = HashMap::new();
 
         let _ = tokio::spawn(async move {
             loop {
@@ -365,11 +383,58 @@ impl VizierChannel for TelegramChannelWriter {
                             });
                             typing_handles.insert(chat_id.0, typing_task);
                         }
+                        VizierResponse {
+                            content: VizierResponseContent::MessageDelta(delta),
+                            timestamp: _,
+                            attachments: _,
+                        } => {
+                            let state = stream_state.entry(chat_id.0).or_insert(StreamState {
+                                message_id: None,
+                                content: String::new(),
+                                last_edit: Instant::now(),
+                            });
+                            state.content.push_str(&delta);
+
+                            if state.content.trim().is_empty()
+                                || (state.message_id.is_some()
+                                    && state.last_edit.elapsed() < STREAM_EDIT_INTERVAL)
+                            {
+                                continue;
+                            }
+
+                            if let Ok(message_id) = crate::utils::telegram::stream_message(
+                                &bot,
+                                chat_id,
+                                state.message_id,
+                                &remove_think_tags(&state.content),
+                            )
+                            .await
+                            {
+                                state.message_id = Some(message_id);
+                                state.last_edit = Instant::now();
+                            }
+                        }
                         VizierResponse {
                             content: VizierResponseContent::ToolChoice { name, args },
                             timestamp: _,
                             attachments: _,
                         } => {
+                            // text streamed before a tool call stays as its own message
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                content,
+                                ..
+                            }) = stream_state.remove(&chat_id.0)
+                            {
+                                let _ = crate::utils::telegram::finalize_stream_message(
+                                    &bot,
+                                    chat_id,
+                                    message_id,
+                                    remove_think_tags(&content),
+                                )
+                                .await;
+                            }
+
                             let _ = crate::utils::telegram::send_message(
                                 &bot,
                                 chat_id,
@@ -377,11 +442,70 @@ impl VizierChannel for TelegramChannelWriter {
                             )
                             .await;
                         }
+                        VizierResponse {
+                            content: VizierResponseContent::ApprovalRequest { id, name, args },
+                            timestamp: _,
+                            attachments: _,
+                        } => {
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                content,
+                                ..
+                            }) = stream_state.remove(&chat_id.0)
+                            {
+                                let _ = crate::utils::telegram::finalize_stream_message(
+                                    &bot,
+                                    chat_id,
+                                    message_id,
+                                    remove_think_tags(&content),
+                                )
+                                .await;
+                            }
+
+                            match crate::utils::telegram::send_approval_request(
+                                &bot,
+                                chat_id,
+                                &id,
+                                crate::utils::format_approval_request(&name, &args),
+                            )
+                            .await
+                            {
+                                Ok(message_id) => {
+                                    approval_requests.insert(id, message_id);
+                                }
+                                Err(err) => log::error!("{}", err),
+                            }
+                        }
+                        VizierResponse {
+                            content: VizierResponseContent::ApprovalResolved { id, .. },
+                            timestamp: _,
+                            attachments: _,
+                        } => {
+                            // the command's reply says who answered
+                            if let Some(message_id) = approval_requests.remove(&id) {
+                                let _ = bot.edit_message_reply_markup(chat_id, message_id).await;
+                            }
+                        }
                         VizierResponse {
                             content: VizierResponseContent::Thinking(thought),
                             timestamp: _,
                             attachments: _,
                         } => {
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                content,
+                                ..
+                            }) = stream_state.remove(&chat_id.0)
+                            {
+                                let _ = crate::utils::telegram::finalize_stream_message(
+                                    &bot,
+                                    chat_id,
+                                    message_id,
+                                    remove_think_tags(&content),
+                                )
+                                .await;
+                            }
+
                             let _ = crate::utils::telegram::send_message(
                                 &bot,
                                 chat_id,
@@ -392,14 +516,35 @@ impl VizierChannel for TelegramChannelWriter {
                         VizierResponse {
                             content: VizierResponseContent::Message { content, stats: _ },
                             timestamp: _,
-                            attachments: _,
+                            attachments,
                         } => {
                             if let Some(handle) = typing_handles.remove(&chat_id.0) {
                                 handle.abort();
                             }
                             let content = remove_think_tags(&content.clone());
-                            let _ =
-                                crate::utils::telegram::send_message(&bot, chat_id, content).await;
+                            if let Some(StreamState {
+                                message_id: Some(message_id),
+                                ..
+                            }) = stream_state.remove(&chat_id.0)
+                            {
+                                let _ = crate::utils::telegram::finalize_stream_message(
+                                    &bot, chat_id, message_id, content,
+                                )
+                                .await;
+                            } else {
+                                let _ =
+                                    crate::utils::telegram::send_message(&bot, chat_id, content)
+                                        .await;
+                            }
+
+                            if !attachments.is_empty() {
+                                let _ = crate::utils::telegram::send_attachments(
+                                    &bot,
+                                    chat_id,
+                                    attachments,
+                                )
+                                .await;
+                            }
                         }
                         VizierResponse {
                             content: VizierResponseContent::Abort,
@@ -409,6 +554,7 @@ impl VizierChannel for TelegramChannelWriter {
                             if let Some(handle) = typing_handles.remove(&chat_id.0) {
                                 handle.abort();
                             }
+                            stream_state.remove(&chat_id.0);
                             let _ = crate::utils::telegram::send_message(
                                 &bot,
                                 chat_id,
@@ -426,8 +572,3 @@ impl VizierChannel for TelegramChannelWriter {
         Ok(())
     }
 }
-
-#[derive(Debug, Deserialize, Serialize)]
-struct ChannelState {
-    active_topic: Option<TopicId>,
-}
@@ -8,7 +8,11 @@ use inquire::{Confirm, CustomType, Select, Text};
 use crate::{
     config::{
         VizierConfig,
-        agent::{AgentConfig, AgentToolsConfig, MemoryConfig, ToolConfig},
+        agent::{
+            AgentConfig, AgentToolsConfig, ArtifactConfig, ConcurrencyPolicy, ContextConfig,
+            EgressConfig, FileToolsConfig, MemoryConfig, PythonLimitsConfig, PythonSessionConfig,
+            RetryConfig, ToolConfig,
+        },
         provider::ProviderVariant,
     },
     constant::AGENT_TEMPLATE,
@@ -55,7 +59,7 @@ pub async fn agent_new(args: AgentNewArgs) -> Result<()> {
 
     let agent_name = Text::new("Agent name:").with_default("MyAgent").prompt()?;
 
-    let provider_names: Vec<String> = vec![
+    let mut provider_names: Vec<String> = vec![
         "ollama".to_string(),
         "deepseek".to_string(),
         "openrouter".to_string(),
@@ -63,10 +67,17 @@ pub async fn agent_new(args: AgentNewArgs) -> Result<()> {
         "openai".to_string(),
         "gemini".to_string(),
     ];
+    provider_names.extend(config.providers.compatible.keys().cloned());
 
     let primary_provider_str = Select::new("Select provider:", provider_names.clone()).prompt()?;
 
-    let primary_provider = if primary_provider_str.contains("ollama") {
+    let primary_provider = if config
+        .providers
+        .compatible
+        .contains_key(&primary_provider_str)
+    {
+        ProviderVariant::compatible(primary_provider_str.clone())
+    } else if primary_provider_str.contains("ollama") {
         ProviderVariant::ollama
     } else if primary_provider_str.contains("deepseek") {
         ProviderVariant::deepseek
@@ -87,6 +98,7 @@ pub async fn agent_new(args: AgentNewArgs) -> Result<()> {
         ProviderVariant::anthropic => "claude-3-haiku-20240307",
         ProviderVariant::openai => "gpt-4o-mini",
         ProviderVariant::gemini => "gemini-2.0-flash",
+        ProviderVariant::mock | ProviderVariant::compatible(_) => "",
     };
 
     let model = Text::new("Model:").with_default(default_model).prompt()?;
@@ -142,13 +154,19 @@ pub async fn agent_new(args: AgentNewArgs) -> Result<()> {
         },
         provider: primary_provider.clone(),
         model: model.clone(),
+        fallbacks: vec![],
+        retry: RetryConfig::default(),
+        context: ContextConfig::default(),
         session_memory: MemoryConfig {
             max_capacity: memory_capacity,
         },
         thinking_depth,
         tools: AgentToolsConfig {
             timeout: DurationString::from_string("1m".into()).unwrap(),
+            max_concurrent_calls: None,
             programmatic_sandbox: false,
+            python_sessions: PythonSessionConfig::default(),
+            python_limits: PythonLimitsConfig::default(),
             shell_access,
             brave_search: ToolConfig {
                 enabled: brave_search_enabled,
@@ -160,10 +178,20 @@ pub async fn agent_new(args: AgentNewArgs) -> Result<()> {
                 enabled: discord_enabled,
             },
             telegram: ToolConfig { enabled: false },
+            web_search: None,
             notify_primary_user: ToolConfig { enabled: true },
             fetch: ToolConfig { enabled: false },
             http_client: ToolConfig { enabled: false },
+            files: FileToolsConfig::default(),
             mcp_servers: vec![],
+            openapi: vec![],
+            allow: vec![],
+            deny: vec![],
+            require_approval: vec![],
+            approval_timeout: None,
+            permissions: None,
+            egress: EgressConfig::default(),
+            artifacts: ArtifactConfig::default(),
         },
         silent_read_initiative_chance: 0.,
         show_thinking: Some(false),
@@ -173,6 +201,9 @@ pub async fn agent_new(args: AgentNewArgs) -> Result<()> {
         heartbeat_interval: DurationString::from_string("30m".into()).unwrap(),
         dream_interval: DurationString::from_string("24h".into()).unwrap(),
         show_tool_calls: None,
+        stream_response: None,
+        concurrency_policy: ConcurrencyPolicy::default(),
+        budget: None,
     };
 
     println!("\n========== Agent Preview ==========\n");
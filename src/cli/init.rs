@@ -6,7 +6,11 @@ use duration_string::DurationString;
 use crate::{
     config::{
         VizierConfig,
-        agent::{AgentConfig, AgentToolsConfig, MemoryConfig, ToolConfig},
+        agent::{
+            AgentConfig, AgentToolsConfig, ArtifactConfig, ConcurrencyPolicy, ContextConfig,
+            EgressConfig, FileToolsConfig, MemoryConfig, PythonLimitsConfig, PythonSessionConfig,
+            RetryConfig, ToolConfig,
+        },
     },
     constant::AGENT_TEMPLATE,
     utils::build_path,
@@ -36,6 +40,9 @@ pub fn init_default_agent(path: PathBuf) {
         name: "Vizier".to_string(),
         system_prompt: None,
         model: "qwen3.5:4b".into(),
+        fallbacks: vec![],
+        retry: RetryConfig::default(),
+        context: ContextConfig::default(),
         description: Some("Digital steward".into()),
         provider: crate::config::provider::ProviderVariant::ollama,
         prompt_timeout: DurationString::from_string("5m".into()).unwrap(),
@@ -43,16 +50,29 @@ pub fn init_default_agent(path: PathBuf) {
         thinking_depth: 10,
         tools: AgentToolsConfig {
             programmatic_sandbox: false,
+            python_sessions: PythonSessionConfig::default(),
+            python_limits: PythonLimitsConfig::default(),
             timeout: DurationString::from_string("1m".into()).unwrap(),
+            max_concurrent_calls: None,
             shell_access: false,
             brave_search: ToolConfig { enabled: false },
             vector_memory: ToolConfig { enabled: true },
             discord: ToolConfig { enabled: false },
             telegram: ToolConfig { enabled: false },
+            web_search: None,
             notify_primary_user: ToolConfig { enabled: true },
             fetch: ToolConfig { enabled: false },
             http_client: ToolConfig { enabled: false },
+            files: FileToolsConfig::default(),
             mcp_servers: vec![],
+            openapi: vec![],
+            allow: vec![],
+            deny: vec![],
+            require_approval: vec![],
+            approval_timeout: None,
+            permissions: None,
+            egress: EgressConfig::default(),
+            artifacts: ArtifactConfig::default(),
         },
         silent_read_initiative_chance: 0.,
         show_thinking: Some(false),
@@ -61,6 +81,9 @@ pub fn init_default_agent(path: PathBuf) {
         heartbeat_interval: DurationString::from_string("30m".into()).unwrap(),
         dream_interval: DurationString::from_string("24h".into()).unwrap(),
         show_tool_calls: None,
+        stream_response: None,
+        concurrency_policy: ConcurrencyPolicy::default(),
+        budget: None,
     };
 
     let content = format!(
@@ -9,7 +9,11 @@ use crate::{
     config::{
         ChannelsConfig, DiscordChannelConfig, HTTPChannelConfig, TelegramChannelConfig,
         VizierConfig,
-        agent::{AgentConfig, AgentToolsConfig, MemoryConfig, ToolConfig},
+        agent::{
+            AgentConfig, AgentToolsConfig, ArtifactConfig, ConcurrencyPolicy, ContextConfig,
+            EgressConfig, FileToolsConfig, MemoryConfig, PythonLimitsConfig, PythonSessionConfig,
+            RetryConfig, ToolConfig,
+        },
         provider::{ProviderConfig, ProviderVariant},
         storage::{DocumentIndexerConfig, StorageConfig},
         tools::{BraveSearchConfig, ToolsConfig},
@@ -145,6 +149,7 @@ pub async fn onboard(args: OnboardArgs) -> Result<()> {
         ProviderVariant::anthropic => "claude-3-haiku-20240307",
         ProviderVariant::openai => "gpt-4o-mini",
         ProviderVariant::gemini => "gemini-2.0-flash",
+        ProviderVariant::mock | ProviderVariant::compatible(_) => "",
     };
 
     let agent_name = Text::new("Agent name:").with_default("Vizier").prompt()?;
@@ -289,6 +294,7 @@ pub async fn onboard(args: OnboardArgs) -> Result<()> {
             discord_id: "".into(),
             discord_username: "".into(),
             telegram_username: "".into(),
+            telegram_id: "".into(),
             alias: vec![],
         },
         providers,
@@ -317,6 +323,10 @@ pub async fn onboard(args: OnboardArgs) -> Result<()> {
                 None
             },
             mcp_servers: HashMap::new(),
+            openapi: HashMap::new(),
+            credentials: HashMap::new(),
+            search: HashMap::new(),
+            custom: HashMap::new(),
         },
         shell: crate::config::shell::ShellConfig::Local(crate::config::shell::LocalShellConfig {
             path: ".".into(),
@@ -334,13 +344,19 @@ pub async fn onboard(args: OnboardArgs) -> Result<()> {
         },
         provider: primary_provider,
         model: model.clone(),
+        fallbacks: vec![],
+        retry: RetryConfig::default(),
+        context: ContextConfig::default(),
         session_memory: MemoryConfig {
             max_capacity: memory_capacity,
         },
         thinking_depth,
         tools: AgentToolsConfig {
             programmatic_sandbox: false,
+            python_sessions: PythonSessionConfig::default(),
+            python_limits: PythonLimitsConfig::default(),
             timeout: DurationString::from_string("1m".into()).unwrap(),
+            max_concurrent_calls: None,
             shell_access,
             brave_search: ToolConfig {
                 enabled: brave_search_enabled,
@@ -352,10 +368,20 @@ pub async fn onboard(args: OnboardArgs) -> Result<()> {
                 enabled: discord_enabled,
             },
             telegram: ToolConfig { enabled: false },
+            web_search: None,
             notify_primary_user: ToolConfig { enabled: true },
             fetch: ToolConfig { enabled: false },
             http_client: ToolConfig { enabled: false },
+            files: FileToolsConfig::default(),
             mcp_servers: vec![],
+            openapi: vec![],
+            allow: vec![],
+            deny: vec![],
+            require_approval: vec![],
+            approval_timeout: None,
+            permissions: None,
+            egress: EgressConfig::default(),
+            artifacts: ArtifactConfig::default(),
         },
         silent_read_initiative_chance: 0.,
         show_thinking: Some(false),
@@ -365,6 +391,9 @@ pub async fn onboard(args: OnboardArgs) -> Result<()> {
         heartbeat_interval: DurationString::from_string("30m".into()).unwrap(),
         dream_interval: DurationString::from_string("24h".into()).unwrap(),
         show_tool_calls: None,
+        stream_response: None,
+        concurrency_policy: ConcurrencyPolicy::default(),
+        budget: None,
     };
 
     config.save(config_path.clone(), "".into())?;
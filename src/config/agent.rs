@@ -15,12 +15,24 @@ pub struct AgentConfig {
     pub description: Option<String>,
     pub provider: ProviderVariant,
     pub model: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<ModelFallbackConfig>,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub context: ContextConfig,
     pub session_memory: MemoryConfig,
     pub thinking_depth: usize,
     pub tools: AgentToolsConfig,
     pub silent_read_initiative_chance: f32,
     pub show_thinking: Option<bool>,
     pub show_tool_calls: Option<bool>,
+    pub stream_response: Option<bool>,
+    /// what happens when a request arrives while the session is still busy
+    #[serde(default)]
+    pub concurrency_policy: ConcurrencyPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub include_documents: Option<Vec<String>>,
     pub prompt_timeout: DurationString,
@@ -31,6 +43,20 @@ pub struct AgentConfig {
 }
 
 impl AgentConfig {
+    /// token budget of the prompt, bounded by the smallest known context window
+    /// in the fallback chain since any of them may end up answering
+    pub fn context_budget(&self) -> Option<usize> {
+        std::iter::once(self.context.context_window)
+            .chain(
+                self.fallbacks
+                    .iter()
+                    .map(|fallback| fallback.context_window),
+            )
+            .flatten()
+            .min()
+            .map(|window| window.saturating_sub(self.context.reserved_output_tokens))
+    }
+
     pub fn find_agent_configs(path: PathBuf) -> crate::Result<AgentConfigs> {
         let mut res = AgentConfigs::new();
         // find all .agent.md
@@ -92,6 +118,77 @@ impl AgentConfig {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelFallbackConfig {
+    pub provider: ProviderVariant,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<usize>,
+}
+
+/// spending limits of an agent in USD, priced with `providers.pricing`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BudgetConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly: Option<f64>,
+    /// cheaper model to switch to once a limit is reached, requests are
+    /// refused when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downgrade: Option<ModelFallbackConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetryConfig {
+    pub max_retries: usize,
+    pub initial_backoff: DurationString,
+    pub max_backoff: DurationString,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: DurationString::from_string("1s".into()).unwrap(),
+            max_backoff: DurationString::from_string("30s".into()).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// context window of the primary model in tokens, no budget is enforced when unset
+    pub context_window: Option<usize>,
+    /// tokens kept free for the model's answer
+    pub reserved_output_tokens: usize,
+    /// summarize older turns instead of dropping them
+    pub compaction: bool,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            context_window: None,
+            reserved_output_tokens: 4096,
+            compaction: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrencyPolicy {
+    /// abort the running turn and answer the new request
+    #[default]
+    Interrupt,
+    /// answer requests one after another
+    Queue,
+    /// fold the new request into the running turn at its next tool boundary
+    Merge,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MemoryConfig {
     pub max_capacity: usize,
@@ -100,12 +197,24 @@ pub struct MemoryConfig {
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct AgentToolsConfig {
     pub timeout: DurationString,
+    /// how many tool calls of a single turn may run at once, defaults to 4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_calls: Option<usize>,
     #[serde(default)]
     pub programmatic_sandbox: bool,
+    /// keeps the sandbox's variables and imports between the calls of a session
+    #[serde(default)]
+    pub python_sessions: PythonSessionConfig,
+    /// what the sandbox's scripts may do
+    #[serde(default)]
+    pub python_limits: PythonLimitsConfig,
     #[serde(default)]
     pub shell_access: bool,
     #[serde(default)]
     pub brave_search: ToolConfig,
+    /// `tools.search` backend of the search tools, takes over `brave_search`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_search: Option<String>,
     #[serde(default)]
     pub vector_memory: ToolConfig,
     #[serde(default)]
@@ -118,8 +227,184 @@ pub struct AgentToolsConfig {
     pub fetch: ToolConfig,
     #[serde(default)]
     pub http_client: ToolConfig,
+    /// file tools rooted at the agent workspace
+    #[serde(default)]
+    pub files: FileToolsConfig,
     #[serde(default)]
     pub mcp_servers: Vec<String>,
+    /// names of the `tools.openapi` documents the agent gets tools from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub openapi: Vec<String>,
+    /// tool names the agent may use, every tool when empty. `*` wildcards are
+    /// allowed, also for `mcp_*` and `SKILL__*` names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// tool names the agent may never use, wins over `allow`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// tool names that wait for a human approval before running, `*` wildcards
+    /// are allowed, e.g. `mcp_*`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub require_approval: Vec<String>,
+    /// how long to wait for an approval before denying the call, defaults to 5m
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_timeout: Option<DurationString>,
+    /// tools per sender tier, everyone gets every tool when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<ToolPermissionsConfig>,
+    /// where `fetch`, `http_client` and attachment downloads may connect to
+    #[serde(default)]
+    pub egress: EgressConfig,
+    /// long tool results are stored aside, the model gets a preview
+    #[serde(default)]
+    pub artifacts: ArtifactConfig,
+}
+
+/// the primary user always gets every tool, `*` wildcards are allowed in the
+/// tool lists
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ToolPermissionsConfig {
+    /// discord or telegram user ids of the trusted users
+    #[serde(default)]
+    pub trusted_users: Vec<String>,
+    /// tools trusted users may use
+    #[serde(default)]
+    pub trusted: Vec<String>,
+    /// tools anyone else may use
+    #[serde(default)]
+    pub everyone: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FileToolsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub root: FileToolsRoot,
+    /// only the read, list and grep tools
+    #[serde(default)]
+    pub read_only: bool,
+    /// largest file that can be read, written or patched, defaults to 1MB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_bytes: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileToolsRoot {
+    /// the agent workspace
+    #[default]
+    Workspace,
+    /// the path of the local shell
+    Shell,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PythonSessionConfig {
+    pub enabled: bool,
+    /// how long an unused interpreter is kept before it's dropped
+    pub idle_timeout: DurationString,
+}
+
+impl Default for PythonSessionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_timeout: DurationString::from_string("30m".into()).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PythonLimitsConfig {
+    /// wall-clock time a script may run
+    pub timeout: DurationString,
+    /// characters a script may send to `output`, the rest is dropped
+    pub max_output_chars: usize,
+    /// `open` and the filesystem and process modules
+    pub allow_filesystem: bool,
+    /// the socket and http modules
+    pub allow_network: bool,
+}
+
+impl Default for PythonLimitsConfig {
+    fn default() -> Self {
+        Self {
+            timeout: DurationString::from_string("30s".into()).unwrap(),
+            max_output_chars: 20_000,
+            allow_filesystem: false,
+            allow_network: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ArtifactConfig {
+    /// tool results longer than this many characters become artifacts, 0 keeps
+    /// every result in the conversation
+    pub threshold: usize,
+    /// characters of the result the model still sees
+    pub preview_chars: usize,
+    /// how long artifacts are kept
+    pub ttl: DurationString,
+}
+
+impl Default for ArtifactConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 16_000,
+            preview_chars: 2_000,
+            ttl: DurationString::from_string("24h".into()).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EgressConfig {
+    /// domains that may be reached, `*.example.com` wildcards are allowed, any
+    /// domain when empty
+    pub allowed_domains: Vec<String>,
+    /// addresses that may never be reached, loopback, private and link-local
+    /// ranges by default
+    pub blocked_cidrs: Vec<String>,
+    pub max_response_bytes: usize,
+    pub max_redirects: usize,
+    pub timeout: DurationString,
+}
+
+impl Default for EgressConfig {
+    fn default() -> Self {
+        Self {
+            allowed_domains: vec![],
+            blocked_cidrs: [
+                "0.0.0.0/8",
+                "10.0.0.0/8",
+                "100.64.0.0/10",
+                "127.0.0.0/8",
+                "169.254.0.0/16",
+                "172.16.0.0/12",
+                "192.168.0.0/16",
+                "198.18.0.0/15",
+                "224.0.0.0/4",
+                "::/128",
+                "::1/128",
+                "64:ff9b::/96",
+                "fc00::/7",
+                "fe80::/10",
+                "ff00::/8",
+            ]
+            .into_iter()
+            .map(|cidr| cidr.to_string())
+            .collect(),
+            max_response_bytes: 10 * 1024 * 1024,
+            max_redirects: 5,
+            timeout: DurationString::from_string("30s".into()).unwrap(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
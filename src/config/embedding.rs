@@ -8,6 +8,8 @@ pub enum EmbeddingConfig {
     Ollama { model: String },
     Openai { model: String },
     Gemini { model: String },
+    /// `provider` names an entry of `providers.compatible`
+    Compatible { provider: String, model: String },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -18,7 +18,7 @@ use crate::{
         provider::{OllamaProviderConfig, ProviderConfig},
         shell::{LocalShellConfig, ShellConfig},
         storage::StorageConfig,
-        tools::{BraveSearchConfig, ToolsConfig},
+        tools::{BraveSearchConfig, ToolsConfig, custom::CustomToolConfig},
         user::UserConfig,
     },
     constant,
@@ -120,6 +120,13 @@ impl VizierConfig {
         let _ = fs::create_dir_all(&workspace)?;
         config.vizier.workspace = workspace.to_str().unwrap().to_string();
 
+        let tools = CustomToolConfig::find_tool_configs(parent_path.join("tools"))?;
+        config.vizier.tools.custom = tools;
+
+        for api in config.vizier.tools.openapi.values_mut() {
+            api.spec = parent_path.join(&api.spec).to_string_lossy().to_string();
+        }
+
         let agent_path = parent_path;
         let agents = AgentConfig::find_agent_configs(agent_path)?;
         config.vizier.agents = agents;
@@ -155,6 +162,7 @@ impl Default for VizierConfig {
                 discord_id: "".into(),
                 discord_username: "".into(),
                 telegram_username: "".into(),
+                telegram_id: "".into(),
                 alias: vec![],
             },
             storage: StorageConfig::Filesystem(storage::DocumentIndexerConfig::InMem),
@@ -165,6 +173,8 @@ impl Default for VizierConfig {
                 anthropic: None,
                 openai: None,
                 gemini: None,
+                compatible: HashMap::new(),
+                pricing: HashMap::new(),
             },
             embedding: Some(EmbeddingConfig::Local {
                 model: LocalEmbeddingModelVariant::AllMiniLml6V2,
@@ -186,6 +196,10 @@ impl Default for VizierConfig {
             tools: ToolsConfig {
                 brave_search: Some(BraveSearchConfig::default()),
                 mcp_servers: HashMap::new(),
+                openapi: HashMap::new(),
+                credentials: HashMap::new(),
+                search: HashMap::new(),
+                custom: HashMap::new(),
             },
             shell: ShellConfig::Local(LocalShellConfig {
                 path: ".".into(),
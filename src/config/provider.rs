@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -9,6 +11,11 @@ pub enum ProviderVariant {
     gemini,
     openai,
     anthropic,
+    /// scripted completions for tests, the agent's `model` is the script path
+    mock,
+    /// any other name refers to an entry of `providers.compatible`
+    #[serde(untagged)]
+    compatible(String),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -25,6 +32,56 @@ pub struct ProviderConfig {
     pub openrouter: Option<OpenRouterProviderConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ollama: Option<OllamaProviderConfig>,
+    /// named OpenAI compatible servers, e.g. vLLM, llama.cpp or LiteLLM
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub compatible: HashMap<String, CompatibleProviderConfig>,
+    /// prices keyed by `provider/model`, e.g. `openai/gpt-4o`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub pricing: HashMap<String, ModelPricing>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompatibleProviderConfig {
+    pub base_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// sent with every request, e.g. gateway auth or routing headers
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// whether the served models can call tools
+    #[serde(default = "default_true")]
+    pub tools: bool,
+    /// whether the served models accept images
+    #[serde(default = "default_true")]
+    pub vision: bool,
+}
+
+/// USD per million tokens
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelPricing {
+    pub input: f64,
+    /// rate of input tokens served from the provider's prompt cache, defaults
+    /// to the input rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input: Option<f64>,
+    pub output: f64,
+}
+
+impl ModelPricing {
+    /// cached tokens are counted as part of the input tokens
+    pub fn cost(&self, input_tokens: u64, cached_input_tokens: u64, output_tokens: u64) -> f64 {
+        let cached = cached_input_tokens.min(input_tokens);
+        let uncached = input_tokens - cached;
+
+        (uncached as f64 * self.input
+            + cached as f64 * self.cached_input.unwrap_or(self.input)
+            + output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,40 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// a secret the http tools attach to requests, the model only knows its name
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CredentialConfig {
+    /// urls the credential may be sent to, `*` wildcards are allowed. the
+    /// scheme, host and path are matched separately
+    pub urls: Vec<String>,
+    /// tells the model what the credential is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub auth: HttpAuth,
+}
+
+/// values are usually `${ENV}` references, expanded with the rest of the config
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HttpAuth {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+    Header { name: String, value: String },
+    Query { name: String, value: String },
+}
+
+impl HttpAuth {
+    /// the values to keep out of prompts and history
+    pub fn secrets(&self) -> Vec<String> {
+        match self {
+            Self::Bearer { token } => vec![token.clone()],
+            Self::Basic { username, password } => vec![
+                password.clone(),
+                base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", username, password)),
+            ],
+            Self::Header { value, .. } | Self::Query { value, .. } => vec![value.clone()],
+        }
+    }
+}
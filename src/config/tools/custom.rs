@@ -0,0 +1,103 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::VizierError, utils};
+
+/// a tool declared in a file of the `tools` directory, next to the `.agent.md` files
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomToolConfig {
+    pub name: String,
+    /// defaults to the body of a markdown tool file
+    #[serde(default)]
+    pub description: String,
+    /// JSON Schema of the arguments
+    #[serde(default = "default_input")]
+    pub input: serde_json::Value,
+    pub executor: CustomToolExecutor,
+}
+
+fn default_input() -> serde_json::Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+/// `{{arg}}` placeholders are replaced by the arguments of the call
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CustomToolExecutor {
+    /// runs on the configured shell, arguments are quoted
+    Shell { command: String },
+    /// runs in the python sandbox with the arguments in `args`, relative
+    /// paths start from the tools directory
+    Python { path: String },
+    /// `${ENV}` variables are expanded in the url, headers and body
+    Http {
+        #[serde(default = "default_method")]
+        method: String,
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<String>,
+    },
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+pub type CustomToolConfigs = HashMap<String, CustomToolConfig>;
+
+impl CustomToolConfig {
+    pub fn find_tool_configs(path: PathBuf) -> crate::Result<CustomToolConfigs> {
+        let mut res = CustomToolConfigs::new();
+        if !path.is_dir() {
+            return Ok(res);
+        }
+
+        for entry in fs::read_dir(&path).map_err(|err| VizierError(err.to_string()).into())? {
+            let entry = entry.map_err(|err| VizierError(err.to_string()).into())?;
+
+            let file = entry.path();
+            if !file.is_file() {
+                continue;
+            }
+
+            let tool = match file.extension().and_then(|ext| ext.to_str()) {
+                Some("yaml") | Some("yml") => Self::load_from_yaml(file.clone()),
+                Some("md") => Self::load_from_md(file.clone()),
+                _ => continue,
+            };
+
+            match tool {
+                Ok(mut tool) => {
+                    if let CustomToolExecutor::Python { path: script } = &mut tool.executor {
+                        *script = path.join(&script).to_string_lossy().to_string();
+                    }
+
+                    res.insert(tool.name.clone(), tool);
+                }
+                Err(err) => {
+                    log::warn!("failed to load {}: {}", file.to_str().unwrap(), err);
+                }
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn load_from_yaml(path: PathBuf) -> Result<Self, VizierError> {
+        let raw = fs::read_to_string(path).map_err(|err| VizierError(err.to_string()))?;
+
+        serde_yaml::from_str(&raw).map_err(|err| VizierError(err.to_string()))
+    }
+
+    fn load_from_md(path: PathBuf) -> Result<Self, VizierError> {
+        let (mut tool, content) = utils::markdown::read_markdown::<Self>(path)?;
+        if tool.description.is_empty() {
+            tool.description = content.trim().to_string();
+        }
+
+        Ok(tool)
+    }
+}
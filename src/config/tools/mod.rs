@@ -2,15 +2,43 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::config::tools::mcp::McpClientConfig;
+use crate::config::tools::{
+    credential::CredentialConfig, custom::CustomToolConfigs, mcp::McpClientConfig,
+    openapi::OpenApiConfig, search::SearchBackendConfig,
+};
 
+pub mod credential;
+pub mod custom;
 pub mod mcp;
+pub mod openapi;
+pub mod search;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ToolsConfig {
     #[serde(skip_serializing_if = "HashMap::is_empty", default)]
     pub mcp_servers: HashMap<String, McpClientConfig>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub openapi: HashMap<String, OpenApiConfig>,
+    /// secrets the http tools attach by name
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub credentials: HashMap<String, CredentialConfig>,
     pub brave_search: Option<BraveSearchConfig>,
+    /// named backends of the search tools, agents pick one with `web_search`
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub search: HashMap<String, SearchBackendConfig>,
+    #[serde(skip)]
+    pub custom: CustomToolConfigs,
+}
+
+impl ToolsConfig {
+    /// a backend of `search`, `brave` also names the `brave_search` config
+    pub fn search_backend(&self, name: &str) -> Option<SearchBackendConfig> {
+        match self.search.get(name) {
+            Some(backend) => Some(backend.clone()),
+            None if name == "brave" => self.brave_search.clone().map(SearchBackendConfig::Brave),
+            None => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::tools::credential::HttpAuth;
+
+/// an OpenAPI 3 document whose operations become tools
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OpenApiConfig {
+    /// local path of the document (YAML or JSON), relative to the config file
+    pub spec: String,
+    /// defaults to the first `servers` entry of the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<HttpAuth>,
+    /// name of a `tools.credentials` entry, used when `auth` is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+    /// sent with every request
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::tools::BraveSearchConfig;
+
+/// a backend of the `web_search` and `news_search` tools
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchBackendConfig {
+    Brave(BraveSearchConfig),
+    Searxng(SearxngConfig),
+    Json(JsonSearchConfig),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearxngConfig {
+    /// base url of the instance, its `json` format has to be enabled
+    pub url: String,
+    #[serde(default)]
+    pub safesearch: bool,
+    /// engines to search with, the instance's defaults when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub engines: Vec<String>,
+}
+
+/// any search API answering with JSON
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JsonSearchConfig {
+    /// `{{query}}`, `{{page}}`, `{{offset}}` and `{{count}}` placeholders are
+    /// replaced, url encoded
+    pub url: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// JSON pointer to the list of results, e.g. `/data/items`
+    pub results: String,
+    /// JSON pointers inside a result
+    #[serde(default = "default_title")]
+    pub title: String,
+    #[serde(default = "default_link")]
+    pub link: String,
+    #[serde(default = "default_description")]
+    pub description: String,
+}
+
+fn default_title() -> String {
+    "/title".into()
+}
+
+fn default_link() -> String {
+    "/url".into()
+}
+
+fn default_description() -> String {
+    "/description".into()
+}
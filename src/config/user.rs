@@ -9,5 +9,7 @@ pub struct UserConfig {
     pub discord_username: String,
     #[serde(default)]
     pub telegram_username: String,
+    #[serde(default)]
+    pub telegram_id: String,
     pub alias: Vec<String>,
 }
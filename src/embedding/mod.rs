@@ -3,7 +3,11 @@ use std::sync::Arc;
 use anyhow::Result;
 use rig::client::{EmbeddingsClient, Nothing};
 
-use crate::config::{VizierConfig, embedding::EmbeddingConfig};
+use crate::{
+    config::{VizierConfig, embedding::EmbeddingConfig},
+    error::VizierError,
+    utils::openai::compatible_client,
+};
 
 pub mod fastembed;
 pub mod gemini;
@@ -63,6 +67,20 @@ impl VizierEmbedder {
 
                 Self::build(model)
             }
+            EmbeddingConfig::Compatible { provider, model } => {
+                let provider_config =
+                    config
+                        .providers
+                        .compatible
+                        .get(provider)
+                        .ok_or(VizierError(format!(
+                            "provider `{}` is not configured under providers.compatible",
+                            provider
+                        )))?;
+                let model = compatible_client(provider_config)?.embedding_model(model);
+
+                Self::build(model)
+            }
             EmbeddingConfig::Openrouter { model } => {
                 let model = rig::providers::openrouter::Client::new(
                     config.providers.openrouter.clone().unwrap().api_key,
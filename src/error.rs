@@ -19,3 +19,16 @@ impl<T> From<VizierError> for crate::Result<T> {
         crate::Result::Err(value)
     }
 }
+
+/// raised by a session hook to skip a tool call, the reason is handed back to
+/// the model as the tool result instead of failing the turn
+#[derive(Debug, Clone)]
+pub struct VizierToolDenied(pub String);
+
+impl std::fmt::Display for VizierToolDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for VizierToolDenied {}
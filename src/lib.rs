@@ -0,0 +1,23 @@
+#[allow(unused)]
+#[macro_use]
+extern crate log;
+
+use crate::error::VizierError;
+
+pub type Result<T> = std::result::Result<T, VizierError>;
+
+pub mod agents;
+pub mod channels;
+pub mod cli;
+pub mod config;
+pub mod constant;
+pub mod dependencies;
+pub mod embedding;
+pub mod error;
+pub mod mcp;
+pub mod scheduler;
+pub mod schema;
+pub mod shell;
+pub mod storage;
+pub mod transport;
+pub mod utils;
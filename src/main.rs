@@ -1,29 +1,8 @@
 extern crate pretty_env_logger;
-#[allow(unused)]
-#[macro_use]
-extern crate log;
 
 use std::process;
 
-use crate::error::VizierError;
-
-pub type Result<T> = std::result::Result<T, VizierError>;
-
-mod agents;
-mod channels;
-mod cli;
-mod config;
-mod constant;
-mod dependencies;
-mod embedding;
-mod error;
-mod mcp;
-mod scheduler;
-mod schema;
-mod shell;
-mod storage;
-mod transport;
-mod utils;
+use vizier::{Result, cli};
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use surrealdb_types::SurrealValue;
@@ -15,4 +16,12 @@ pub struct SessionHistory {
 pub enum SessionHistoryContent {
     Request(VizierRequest),
     Response(VizierResponse),
-}
\ No newline at end of file
+}
+impl SessionHistory {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match &self.content {
+            SessionHistoryContent::Request(req) => req.timestamp,
+            SessionHistoryContent::Response(res) => res.timestamp,
+        }
+    }
+}
@@ -7,9 +7,14 @@ use serde::{Deserialize, Serialize};
 pub struct UsageSummary {
     pub total_tokens: u64,
     pub total_input_tokens: u64,
+    #[serde(default)]
+    pub total_cached_input_tokens: u64,
     pub total_output_tokens: u64,
     pub total_requests: u64,
     pub avg_duration_ms: f64,
+    /// USD, unpriced models count as free
+    #[serde(default)]
+    pub total_cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
@@ -17,6 +22,8 @@ pub struct ChannelUsage {
     pub channel_id: String,
     pub total_tokens: u64,
     pub total_requests: u64,
+    #[serde(default)]
+    pub total_cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
@@ -25,6 +32,8 @@ pub struct ChannelTypeUsageDetail {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub total_requests: u64,
+    #[serde(default)]
+    pub cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
@@ -37,6 +46,8 @@ pub struct DailyChannelTypeUsage {
 pub struct ChannelTypeUsage {
     pub total_tokens: u64,
     pub total_requests: u64,
+    #[serde(default)]
+    pub total_cost: f64,
     pub channels: Vec<ChannelUsage>,
 }
 
@@ -47,6 +58,8 @@ pub struct DailyUsage {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub total_requests: u64,
+    #[serde(default)]
+    pub cost: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
@@ -56,4 +69,4 @@ pub struct AgentUsageStats {
     pub by_day: Vec<DailyUsage>,
     #[serde(default)]
     pub by_day_and_channel_type: Vec<DailyChannelTypeUsage>,
-}
\ No newline at end of file
+}
@@ -13,8 +13,13 @@ pub use metrics::{
 };
 pub use request::{VizierAttachment, VizierAttachmentContent, VizierRequest, VizierRequestContent};
 pub use response::{VizierResponse, VizierResponseContent, VizierResponseStats};
-pub use session::{AgentId, TopicId, VizierChannelId, VizierSession, VizierSessionDetail};
-pub use storage::{DocumentIndex, Memory, SharedDocument, SharedDocumentSummary, Skill};
+pub use session::{
+    AgentId, ChannelState, SessionSettings, TopicId, VizierChannelId, VizierSession,
+    VizierSessionDetail,
+};
+pub use storage::{
+    DocumentIndex, Memory, SharedDocument, SharedDocumentSummary, Skill, ToolArtifact,
+};
 pub use task::{Task, TaskSchedule};
 
 use serde::{Deserialize, Serialize};
@@ -25,4 +30,3 @@ struct User {
     pub username: String,
     pub password_hash: String,
 }
-
@@ -61,6 +61,26 @@ pub struct VizierAttachment {
 }
 
 impl VizierAttachment {
+    /// size of the file in bytes, unknown for urls
+    pub fn size(&self) -> Option<usize> {
+        match &self.content {
+            VizierAttachmentContent::Bytes(bytes) => Some(bytes.len()),
+            VizierAttachmentContent::Base64(base64) => Some(base64.len() / 4 * 3),
+            VizierAttachmentContent::Url(_) => None,
+        }
+    }
+
+    /// raw content of the file, `None` for urls
+    pub fn bytes(&self) -> Result<Option<Vec<u8>>> {
+        Ok(match &self.content {
+            VizierAttachmentContent::Bytes(bytes) => Some(bytes.clone()),
+            VizierAttachmentContent::Base64(base64) => {
+                Some(base64::engine::general_purpose::STANDARD.decode(base64)?)
+            }
+            VizierAttachmentContent::Url(_) => None,
+        })
+    }
+
     pub fn to_user_content(&self) -> Result<UserContent> {
         let attachment = self.clone();
         let mime_type = get_mime_type(&attachment.filename);
@@ -111,6 +131,10 @@ pub struct VizierRequest {
     pub metadata: serde_json::Value,
     #[serde(default)]
     pub attachments: Vec<VizierAttachment>,
+    /// JSON Schema the final answer has to match, the answer is then the JSON
+    /// value itself instead of prose
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
 }
 
 impl VizierRequest {
@@ -24,6 +24,29 @@ pub struct VizierResponseStats {
     pub total_output_tokens: u64,
     pub total_tokens: u64,
     pub duration: tokio::time::Duration,
+    /// provider/model that produced the final answer
+    #[serde(default)]
+    pub model: Option<String>,
+    /// USD spent on the turn, only known when the models are priced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    /// what the model reasoned before answering, kept out of the content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+}
+
+impl VizierResponseStats {
+    /// counts the usage of a side completion, like a history summary, in the turn
+    pub fn add_usage(&mut self, other: &VizierResponseStats) {
+        self.total_tokens += other.total_tokens;
+        self.total_cached_input_tokens += other.total_cached_input_tokens;
+        self.total_input_tokens += other.total_input_tokens;
+        self.total_output_tokens += other.total_output_tokens;
+        self.duration += other.duration;
+        if let Some(cost) = other.cost {
+            *self.cost.get_or_insert(0.0) += cost;
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SurrealValue, JsonSchema, utoipa::ToSchema)]
@@ -45,6 +68,19 @@ pub enum VizierResponseContent {
     ToolResponse {
         response: serde_json::Value,
     },
+    /// a tool call waiting for the user, answered with `/approve <id>` or `/deny <id>`
+    ApprovalRequest {
+        id: String,
+        name: String,
+        args: serde_json::Value,
+    },
+    /// an approval request was answered by `by`
+    ApprovalResolved {
+        id: String,
+        approved: bool,
+        by: String,
+    },
+    MessageDelta(String),
     Message {
         content: String,
         stats: Option<VizierResponseStats>,
@@ -84,4 +84,15 @@ pub struct VizierSessionDetail {
     pub channel: VizierChannelId,
     pub topic: Option<TopicId>,
     pub title: String,
-}
\ No newline at end of file
+}
+/// per channel state, e.g. which topic new messages go to
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ChannelState {
+    pub active_topic: Option<TopicId>,
+}
+
+/// per session overrides of the agent config
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SessionSettings {
+    pub show_thinking: Option<bool>,
+}
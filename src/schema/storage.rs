@@ -46,4 +46,14 @@ pub struct SharedDocumentSummary {
     pub title: String,
     pub author_agent_id: AgentId,
     pub timestamp: DateTime<Utc>,
-}
\ No newline at end of file
+}
+
+/// a tool result too long to stay in the conversation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolArtifact {
+    pub id: String,
+    pub agent_id: AgentId,
+    pub tool: String,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
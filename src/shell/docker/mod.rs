@@ -1,6 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Result;
+use base64::Engine;
 use bollard::{
     Docker,
     exec::{StartExecOptions, StartExecResults},
@@ -12,6 +13,7 @@ use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::{
     config::shell::{DockerShellConfig, DockerSourceConfig},
+    error::VizierError,
     shell::ShellProvider,
 };
 
@@ -198,4 +200,23 @@ impl ShellProvider for DockerShell {
 
         Ok(res.iter().map(|s| s.clone()).collect::<Vec<_>>().join("\n"))
     }
+
+    async fn read_file(&self, path: String) -> Result<Vec<u8>> {
+        // exec output is text, so the file goes through base64
+        let quoted = format!("'{}'", path.replace('\'', "'\\''"));
+        let output = self
+            .exec(format!("test -f {0} && base64 {0}", quoted))
+            .await?;
+        let encoded = output
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        if encoded.is_empty() {
+            return Err(VizierError(format!("{} not found in the container", path)).into());
+        }
+
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|_| VizierError(format!("can't read {} from the container", path)).into())
+    }
 }
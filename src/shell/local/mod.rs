@@ -2,7 +2,7 @@ use std::{collections::HashMap, path::PathBuf, process::Command};
 
 use anyhow::Result;
 
-use crate::{config::shell::LocalShellConfig, shell::ShellProvider};
+use crate::{config::shell::LocalShellConfig, error::VizierError, shell::ShellProvider};
 
 pub struct LocalShell {
     workdir: PathBuf,
@@ -34,4 +34,21 @@ impl ShellProvider for LocalShell {
 
         Ok(String::from_utf8(output.stdout)?)
     }
+
+    async fn read_file(&self, path: String) -> Result<Vec<u8>> {
+        let workdir = self.workdir.canonicalize()?;
+        let file = workdir
+            .join(&path)
+            .canonicalize()
+            .map_err(|_| VizierError(format!("{} not found in the shell", path)))?;
+
+        // symlinks are resolved, so they can't lead out of the working directory either
+        if !file.starts_with(&workdir) {
+            return Err(
+                VizierError(format!("{} is outside the shell working directory", path)).into(),
+            );
+        }
+
+        Ok(tokio::fs::read(file).await?)
+    }
 }
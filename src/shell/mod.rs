@@ -13,6 +13,9 @@ pub mod local;
 #[async_trait::async_trait]
 pub trait ShellProvider {
     async fn exec(&self, commands: String) -> Result<String>;
+
+    /// content of a file, relative paths start from the shell working directory
+    async fn read_file(&self, path: String) -> Result<Vec<u8>>;
 }
 
 pub struct VizierShell(Arc<Box<dyn ShellProvider + Sync + Send + 'static>>);
@@ -35,4 +38,8 @@ impl ShellProvider for VizierShell {
     async fn exec(&self, commands: String) -> Result<String> {
         self.0.exec(commands).await
     }
+
+    async fn read_file(&self, path: String) -> Result<Vec<u8>> {
+        self.0.read_file(path).await
+    }
 }
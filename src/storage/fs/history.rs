@@ -203,6 +203,7 @@ impl HistoryStorage for FileSystemStorage {
                                 _ => unimplemented!(),
                             },
                             attachments,
+                            response_schema: None,
                         }),
                         ContentMetadata::response { stats, attachments } => {
                             SessionHistoryContent::Response(VizierResponse {
@@ -269,9 +270,11 @@ impl HistoryStorage for FileSystemStorage {
     ) -> Result<AgentUsageStats> {
         let mut total_tokens: u64 = 0;
         let mut total_input_tokens: u64 = 0;
+        let mut total_cached_input_tokens: u64 = 0;
         let mut total_output_tokens: u64 = 0;
         let mut total_requests: u64 = 0;
         let mut total_duration_ms: u64 = 0;
+        let mut total_cost: f64 = 0.0;
 
         let mut by_channel_type: HashMap<String, ChannelTypeUsage> = HashMap::new();
         let mut by_day: HashMap<NaiveDate, DailyUsage> = HashMap::new();
@@ -311,9 +314,12 @@ impl HistoryStorage for FileSystemStorage {
                     if let Some(stats) = stats {
                         total_tokens += stats.total_tokens;
                         total_input_tokens += stats.total_input_tokens;
+                        total_cached_input_tokens += stats.total_cached_input_tokens;
                         total_output_tokens += stats.total_output_tokens;
                         total_requests += 1;
                         total_duration_ms += stats.duration.as_millis() as u64;
+                        let cost = stats.cost.unwrap_or(0.0);
+                        total_cost += cost;
 
                         let channel_slug = frontmatter.session.1.to_slug();
                         let channel_type = get_channel_type(&channel_slug);
@@ -324,10 +330,12 @@ impl HistoryStorage for FileSystemStorage {
                             .or_insert_with(|| ChannelTypeUsage {
                                 total_tokens: 0,
                                 total_requests: 0,
+                                total_cost: 0.0,
                                 channels: Vec::new(),
                             });
                         channel_entry.total_tokens += stats.total_tokens;
                         channel_entry.total_requests += 1;
+                        channel_entry.total_cost += cost;
 
                         let channel_id = channel_slug.clone();
                         if let Some(ch) = channel_entry
@@ -337,11 +345,13 @@ impl HistoryStorage for FileSystemStorage {
                         {
                             ch.total_tokens += stats.total_tokens;
                             ch.total_requests += 1;
+                            ch.total_cost += cost;
                         } else {
                             channel_entry.channels.push(ChannelUsage {
                                 channel_id,
                                 total_tokens: stats.total_tokens,
                                 total_requests: 1,
+                                total_cost: cost,
                             });
                         }
 
@@ -351,11 +361,13 @@ impl HistoryStorage for FileSystemStorage {
                             input_tokens: 0,
                             output_tokens: 0,
                             total_requests: 0,
+                            cost: 0.0,
                         });
                         day_entry.total_tokens += stats.total_tokens;
                         day_entry.input_tokens += stats.total_input_tokens;
                         day_entry.output_tokens += stats.total_output_tokens;
                         day_entry.total_requests += 1;
+                        day_entry.cost += cost;
 
                         let day_channel_entry = by_day_and_channel_type
                             .entry(date)
@@ -367,11 +379,13 @@ impl HistoryStorage for FileSystemStorage {
                                 input_tokens: 0,
                                 output_tokens: 0,
                                 total_requests: 0,
+                                cost: 0.0,
                             });
                         channel_detail.total_tokens += stats.total_tokens;
                         channel_detail.input_tokens += stats.total_input_tokens;
                         channel_detail.output_tokens += stats.total_output_tokens;
                         channel_detail.total_requests += 1;
+                        channel_detail.cost += cost;
                     }
                 }
             }
@@ -401,9 +415,11 @@ impl HistoryStorage for FileSystemStorage {
             summary: UsageSummary {
                 total_tokens,
                 total_input_tokens,
+                total_cached_input_tokens,
                 total_output_tokens,
                 total_requests,
                 avg_duration_ms,
+                total_cost,
             },
             by_channel_type,
             by_day: by_day_vec,
@@ -478,6 +494,7 @@ impl HistoryStorage for FileSystemStorage {
                                 _ => unimplemented!(),
                             },
                             attachments,
+                            response_schema: None,
                         }),
                         ContentMetadata::response { stats, attachments } => {
                             SessionHistoryContent::Response(VizierResponse {
@@ -30,4 +30,13 @@ impl StateStorage for FileSystemStorage {
 
         Ok(None)
     }
+
+    async fn delete_state(&self, key: String) -> Result<()> {
+        let path = build_path(&self.workspace, &[STATE_PATH, &format!("{}.json", key)]);
+
+        match std::fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
 }
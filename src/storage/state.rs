@@ -1,11 +1,114 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 
-use crate::storage::VizierStorage;
+use crate::{
+    schema::{
+        AgentId, ChannelState, SessionSettings, ToolArtifact, TopicId, VizierChannelId,
+        VizierSession,
+    },
+    storage::VizierStorage,
+};
 
 #[async_trait::async_trait]
 pub trait StateStorage {
     async fn save_state(&self, key: String, value: serde_json::Value) -> Result<()>;
     async fn get_state(&self, key: String) -> Result<Option<serde_json::Value>>;
+    async fn delete_state(&self, key: String) -> Result<()>;
+
+    async fn get_active_topic(
+        &self,
+        agent_id: AgentId,
+        channel: VizierChannelId,
+    ) -> Result<Option<TopicId>> {
+        let key = format!("{}__{}", agent_id, channel.to_slug());
+        Ok(match self.get_state(key).await? {
+            Some(value) => serde_json::from_value::<ChannelState>(value)?.active_topic,
+            None => None,
+        })
+    }
+
+    async fn set_active_topic(
+        &self,
+        agent_id: AgentId,
+        channel: VizierChannelId,
+        topic: Option<TopicId>,
+    ) -> Result<()> {
+        let key = format!("{}__{}", agent_id, channel.to_slug());
+        self.save_state(
+            key,
+            serde_json::to_value(ChannelState {
+                active_topic: topic,
+            })?,
+        )
+        .await
+    }
+
+    async fn get_session_settings(&self, session: VizierSession) -> Result<SessionSettings> {
+        let key = format!("{}__settings", session.to_slug());
+        Ok(match self.get_state(key).await? {
+            Some(value) => serde_json::from_value(value)?,
+            None => SessionSettings::default(),
+        })
+    }
+
+    async fn save_session_settings(
+        &self,
+        session: VizierSession,
+        settings: SessionSettings,
+    ) -> Result<()> {
+        let key = format!("{}__settings", session.to_slug());
+        self.save_state(key, serde_json::to_value(settings)?).await
+    }
+
+    async fn save_artifact(&self, artifact: ToolArtifact) -> Result<()> {
+        // the index lets expired artifacts be found without listing the storage
+        let index_key = format!("{}__artifacts", artifact.agent_id);
+        let mut index = match self.get_state(index_key.clone()).await? {
+            Some(value) => serde_json::from_value::<Vec<(String, DateTime<Utc>)>>(value)?,
+            None => vec![],
+        };
+        index.push((artifact.id.clone(), artifact.timestamp));
+
+        let key = format!("{}__artifact__{}", artifact.agent_id, artifact.id);
+        self.save_state(key, serde_json::to_value(artifact)?)
+            .await?;
+        self.save_state(index_key, serde_json::to_value(index)?)
+            .await
+    }
+
+    async fn get_artifact(&self, agent_id: AgentId, id: String) -> Result<Option<ToolArtifact>> {
+        let key = format!("{}__artifact__{}", agent_id, id);
+        Ok(match self.get_state(key).await? {
+            Some(value) => Some(serde_json::from_value(value)?),
+            None => None,
+        })
+    }
+
+    /// deletes the agent's artifacts stored before `before`
+    async fn delete_artifacts_before(
+        &self,
+        agent_id: AgentId,
+        before: DateTime<Utc>,
+    ) -> Result<()> {
+        let index_key = format!("{}__artifacts", agent_id);
+        let Some(value) = self.get_state(index_key.clone()).await? else {
+            return Ok(());
+        };
+        let (expired, kept): (Vec<_>, Vec<_>) =
+            serde_json::from_value::<Vec<(String, DateTime<Utc>)>>(value)?
+                .into_iter()
+                .partition(|(_, timestamp)| *timestamp < before);
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        for (id, _) in expired {
+            self.delete_state(format!("{}__artifact__{}", agent_id, id))
+                .await?;
+        }
+        self.save_state(index_key, serde_json::to_value(kept)?)
+            .await
+    }
 }
 
 #[async_trait::async_trait]
@@ -17,4 +120,8 @@ impl StateStorage for VizierStorage {
     async fn get_state(&self, key: String) -> Result<Option<serde_json::Value>> {
         self.0.get_state(key).await
     }
+
+    async fn delete_state(&self, key: String) -> Result<()> {
+        self.0.delete_state(key).await
+    }
 }
@@ -93,9 +93,11 @@ impl HistoryStorage for SurrealStorage {
     ) -> Result<AgentUsageStats> {
         let mut total_tokens: u64 = 0;
         let mut total_input_tokens: u64 = 0;
+        let mut total_cached_input_tokens: u64 = 0;
         let mut total_output_tokens: u64 = 0;
         let mut total_requests: u64 = 0;
         let mut total_duration_ms: u64 = 0;
+        let mut total_cost: f64 = 0.0;
 
         let mut by_channel_type: HashMap<String, ChannelTypeUsage> = HashMap::new();
         let mut by_day: HashMap<NaiveDate, DailyUsage> = HashMap::new();
@@ -138,9 +140,12 @@ impl HistoryStorage for SurrealStorage {
                     if let Some(stats) = stats {
                         total_tokens += stats.total_tokens;
                         total_input_tokens += stats.total_input_tokens;
+                        total_cached_input_tokens += stats.total_cached_input_tokens;
                         total_output_tokens += stats.total_output_tokens;
                         total_requests += 1;
                         total_duration_ms += stats.duration.as_millis() as u64;
+                        let cost = stats.cost.unwrap_or(0.0);
+                        total_cost += cost;
 
                         let channel_slug = history.vizier_session.1.to_slug();
                         let channel_type = get_channel_type(&channel_slug);
@@ -151,10 +156,12 @@ impl HistoryStorage for SurrealStorage {
                             .or_insert_with(|| ChannelTypeUsage {
                                 total_tokens: 0,
                                 total_requests: 0,
+                                total_cost: 0.0,
                                 channels: Vec::new(),
                             });
                         channel_entry.total_tokens += stats.total_tokens;
                         channel_entry.total_requests += 1;
+                        channel_entry.total_cost += cost;
 
                         let channel_id = channel_slug.clone();
                         if let Some(ch) = channel_entry
@@ -164,11 +171,13 @@ impl HistoryStorage for SurrealStorage {
                         {
                             ch.total_tokens += stats.total_tokens;
                             ch.total_requests += 1;
+                            ch.total_cost += cost;
                         } else {
                             channel_entry.channels.push(ChannelUsage {
                                 channel_id,
                                 total_tokens: stats.total_tokens,
                                 total_requests: 1,
+                                total_cost: cost,
                             });
                         }
 
@@ -178,11 +187,13 @@ impl HistoryStorage for SurrealStorage {
                             input_tokens: 0,
                             output_tokens: 0,
                             total_requests: 0,
+                            cost: 0.0,
                         });
                         day_entry.total_tokens += stats.total_tokens;
                         day_entry.input_tokens += stats.total_input_tokens;
                         day_entry.output_tokens += stats.total_output_tokens;
                         day_entry.total_requests += 1;
+                        day_entry.cost += cost;
 
                         let day_channel_entry = by_day_and_channel_type
                             .entry(date)
@@ -194,11 +205,13 @@ impl HistoryStorage for SurrealStorage {
                                 input_tokens: 0,
                                 output_tokens: 0,
                                 total_requests: 0,
+                                cost: 0.0,
                             });
                         channel_detail.total_tokens += stats.total_tokens;
                         channel_detail.input_tokens += stats.total_input_tokens;
                         channel_detail.output_tokens += stats.total_output_tokens;
                         channel_detail.total_requests += 1;
+                        channel_detail.cost += cost;
                     }
                 }
             }
@@ -228,9 +241,11 @@ impl HistoryStorage for SurrealStorage {
             summary: UsageSummary {
                 total_tokens,
                 total_input_tokens,
+                total_cached_input_tokens,
                 total_output_tokens,
                 total_requests,
                 avg_duration_ms,
+                total_cost,
             },
             by_channel_type,
             by_day: by_day_vec,
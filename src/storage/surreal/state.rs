@@ -21,4 +21,10 @@ impl StateStorage for SurrealStorage {
 
         Ok(value)
     }
+
+    async fn delete_state(&self, key: String) -> Result<()> {
+        let _: Option<serde_json::Value> = self.conn.delete(("state", key)).await?;
+
+        Ok(())
+    }
 }
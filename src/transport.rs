@@ -19,6 +19,12 @@ pub struct VizierTransport {
     )>,
 }
 
+impl Default for VizierTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VizierTransport {
     pub fn new() -> Self {
         let mut request_channel = broadcast(1000);
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use reqwest::{Client, Method, RequestBuilder, Url, redirect};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    config::tools::credential::{CredentialConfig, HttpAuth},
+    error::VizierError,
+};
+
+/// the configured credentials, the http tools attach them by name so the
+/// secrets never go through the model
+#[derive(Debug, Clone, Default)]
+pub struct CredentialVault {
+    credentials: HashMap<String, CredentialConfig>,
+}
+
+impl CredentialVault {
+    pub fn new(credentials: HashMap<String, CredentialConfig>) -> Self {
+        Self { credentials }
+    }
+
+    /// lists the credentials for a tool description, empty without credentials
+    pub fn describe(&self) -> String {
+        if self.credentials.is_empty() {
+            return String::new();
+        }
+
+        let mut names = self.credentials.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let list = names
+            .into_iter()
+            .map(|name| {
+                let credential = &self.credentials[name];
+                let description = credential
+                    .description
+                    .as_ref()
+                    .map(|description| format!(" ({})", description))
+                    .unwrap_or_default();
+
+                format!("- {}{}: {}", name, description, credential.urls.join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!(
+            "\n\nConfigured credentials, pass the name in `credential` instead of writing auth headers yourself:\n{}",
+            list
+        )
+    }
+
+    /// the credential, as long as it may be sent to the url
+    pub fn get(&self, name: &str, url: &str) -> Result<&HttpAuth, VizierError> {
+        let credential = self
+            .credentials
+            .get(name)
+            .ok_or(VizierError(format!("credential {} not found", name)))?;
+
+        let parsed = Url::parse(url).map_err(|err| VizierError(format!("{}: {}", url, err)))?;
+        if !credential
+            .urls
+            .iter()
+            .any(|pattern| matches_url(pattern, &parsed))
+        {
+            return Err(VizierError(format!(
+                "credential {} may not be sent to {}",
+                name, url
+            )));
+        }
+
+        Ok(&credential.auth)
+    }
+
+    /// builds a request, authenticated with the credential when one is named
+    pub fn request(
+        &self,
+        client: &Client,
+        method: Method,
+        url: &str,
+        credential: Option<&str>,
+    ) -> Result<RequestBuilder, VizierError> {
+        match credential {
+            Some(name) => authorize(client, method, url, self.get(name, url)?),
+            None => Ok(client.request(method, url)),
+        }
+    }
+
+    /// replaces every secret of the vault found in the text
+    pub fn redact(&self, text: &str) -> String {
+        let mut res = text.to_string();
+        for (name, credential) in &self.credentials {
+            for secret in credential.auth.secrets() {
+                if !secret.is_empty() {
+                    res = res.replace(&secret, &format!("[credential:{}]", name));
+                }
+            }
+        }
+
+        res
+    }
+
+    /// redacts every string of a serializable value
+    pub fn redact_value<T: Serialize + DeserializeOwned>(&self, value: T) -> T {
+        if self.credentials.is_empty() {
+            return value;
+        }
+
+        let Ok(mut json) = serde_json::to_value(&value) else {
+            return value;
+        };
+        self.redact_json(&mut json);
+
+        serde_json::from_value(json).unwrap_or(value)
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => *text = self.redact(text),
+            serde_json::Value::Array(items) => {
+                items.iter_mut().for_each(|item| self.redact_json(item))
+            }
+            serde_json::Value::Object(object) => {
+                object.values_mut().for_each(|item| self.redact_json(item))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// matches the scheme, host and path of the url on their own, so a wildcard
+/// in the path can't stretch over to another host. `*` in the host stays
+/// within it, the query is ignored
+fn matches_url(pattern: &str, url: &Url) -> bool {
+    let Some((scheme, rest)) = pattern.split_once("://") else {
+        return false;
+    };
+    let (host, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    let Some(url_host) = url.host_str() else {
+        return false;
+    };
+    let url_host = match url.port() {
+        Some(port) => format!("{}:{}", url_host, port),
+        None => url_host.to_string(),
+    };
+
+    let matches = |pattern: &str, value: &str| {
+        glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(value))
+    };
+    matches(scheme, url.scheme())
+        && matches(&host.to_lowercase(), &url_host)
+        && matches(path, url.path())
+}
+
+/// whether the redirect leaves the host of the first request, where a
+/// credential attached to the request must not follow
+pub fn leaves_host(attempt: &redirect::Attempt) -> bool {
+    attempt
+        .previous()
+        .first()
+        .is_some_and(|first| first.host_str() != attempt.url().host_str())
+}
+
+/// the default redirects, short of the ones that leave the host
+pub fn credential_redirects() -> redirect::Policy {
+    redirect::Policy::custom(|attempt| {
+        if leaves_host(&attempt) {
+            attempt.stop()
+        } else {
+            redirect::Policy::default().redirect(attempt)
+        }
+    })
+}
+
+/// builds a request with the auth applied
+pub fn authorize(
+    client: &Client,
+    method: Method,
+    url: &str,
+    auth: &HttpAuth,
+) -> Result<RequestBuilder, VizierError> {
+    let mut url = Url::parse(url).map_err(|err| VizierError(format!("{}: {}", url, err)))?;
+    if let HttpAuth::Query { name, value } = auth {
+        url.query_pairs_mut().append_pair(name, value);
+    }
+
+    let request = client.request(method, url);
+    Ok(match auth {
+        HttpAuth::Bearer { token } => request.bearer_auth(token),
+        HttpAuth::Basic { username, password } => request.basic_auth(username, Some(password)),
+        HttpAuth::Header { name, value } => request.header(name, value),
+        HttpAuth::Query { .. } => request,
+    })
+}
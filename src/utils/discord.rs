@@ -1,16 +1,26 @@
 use std::sync::Arc;
 
-use serenity::all::{ChannelId, Http};
+use serenity::all::{
+    ButtonStyle, ChannelId, CreateActionRow, CreateAttachment, CreateButton, CreateMessage,
+    EditMessage, Http, MessageId,
+};
 use text_splitter::MarkdownSplitter;
 
-use crate::error::{VizierError, throw_vizier_error};
+use crate::{
+    error::{VizierError, throw_vizier_error},
+    schema::{VizierAttachment, VizierAttachmentContent},
+    utils::{split_oversized_attachments, truncate_head},
+};
+
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
 
 pub async fn send_message(
     http: Arc<Http>,
     channel_id: &ChannelId,
     content: String,
 ) -> Result<(), VizierError> {
-    if content.len() < 2000 {
+    if content.len() < MAX_MESSAGE_LENGTH {
         let channel_id = channel_id.clone();
         let content = content.clone();
         if let Err(err) = channel_id.say(&http, content.clone()).await {
@@ -20,7 +30,7 @@ pub async fn send_message(
         return Ok(());
     }
 
-    let splitter = MarkdownSplitter::new(2000);
+    let splitter = MarkdownSplitter::new(MAX_MESSAGE_LENGTH);
     let content = content.clone();
     let chunks = splitter
         .chunks(&content)
@@ -43,3 +53,158 @@ pub async fn send_message(
 
     Ok(())
 }
+
+/// send or edit the in-progress message of a streamed response, returns the
+/// message id to be edited on the next delta
+pub async fn stream_message(
+    http: Arc<Http>,
+    channel_id: &ChannelId,
+    message_id: Option<MessageId>,
+    content: &str,
+) -> Result<MessageId, VizierError> {
+    let preview = truncate_head(content, MAX_MESSAGE_LENGTH - 1);
+
+    let res = match message_id {
+        None => channel_id.say(&http, preview).await,
+        Some(message_id) => {
+            channel_id
+                .edit_message(&http, message_id, EditMessage::new().content(preview))
+                .await
+        }
+    };
+
+    match res {
+        Ok(message) => Ok(message.id),
+        Err(err) => throw_vizier_error("streaming message", err),
+    }
+}
+
+/// replace the streamed message with the final content, falls back to a fresh
+/// message when the content no longer fits into a single discord message
+pub async fn finalize_stream_message(
+    http: Arc<Http>,
+    channel_id: &ChannelId,
+    message_id: MessageId,
+    content: String,
+) -> Result<(), VizierError> {
+    if content.len() < MAX_MESSAGE_LENGTH {
+        if let Err(err) = channel_id
+            .edit_message(&http, message_id, EditMessage::new().content(content))
+            .await
+        {
+            log::error!("{:?}", err);
+        }
+
+        return Ok(());
+    }
+
+    if let Err(err) = channel_id.delete_message(&http, message_id).await {
+        log::error!("{:?}", err);
+    }
+
+    send_message(http, channel_id, content).await
+}
+
+/// approval prompt with approve/deny buttons, answered through the
+/// `approve:<id>` / `deny:<id>` component interactions
+pub async fn send_approval_request(
+    http: Arc<Http>,
+    channel_id: &ChannelId,
+    approval_id: &str,
+    content: String,
+) -> Result<MessageId, VizierError> {
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("approve:{}", approval_id))
+            .label("Approve")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("deny:{}", approval_id))
+            .label("Deny")
+            .style(ButtonStyle::Danger),
+    ]);
+
+    match channel_id
+        .send_message(
+            &http,
+            CreateMessage::new()
+                .content(content)
+                .components(vec![buttons]),
+        )
+        .await
+    {
+        Ok(message) => Ok(message.id),
+        Err(err) => throw_vizier_error("sending approval request", err),
+    }
+}
+
+/// drops the buttons of an answered approval request and says who answered
+pub async fn resolve_approval_request(
+    http: Arc<Http>,
+    channel_id: &ChannelId,
+    message_id: MessageId,
+    content: String,
+    approved: bool,
+    by: &str,
+) -> Result<(), VizierError> {
+    let content = format!(
+        "{}\n{} by {}",
+        content,
+        if approved { "approved" } else { "denied" },
+        by
+    );
+
+    if let Err(err) = channel_id
+        .edit_message(
+            &http,
+            message_id,
+            EditMessage::new().content(content).components(vec![]),
+        )
+        .await
+    {
+        return throw_vizier_error("resolving approval request", err);
+    }
+
+    Ok(())
+}
+
+/// upload the files of a response, one message per file
+pub async fn send_attachments(
+    http: Arc<Http>,
+    channel_id: &ChannelId,
+    attachments: Vec<VizierAttachment>,
+) -> Result<(), VizierError> {
+    let (attachments, note) = split_oversized_attachments(attachments, MAX_ATTACHMENT_SIZE);
+
+    for attachment in attachments {
+        let file = match &attachment.content {
+            VizierAttachmentContent::Url(url) => CreateAttachment::url(&http, url).await,
+            _ => match attachment.bytes() {
+                Ok(bytes) => Ok(CreateAttachment::bytes(
+                    bytes.unwrap_or_default(),
+                    attachment.filename.clone(),
+                )),
+                Err(err) => {
+                    log::error!("{}: {}", attachment.filename, err);
+                    continue;
+                }
+            },
+        };
+
+        match file {
+            Ok(file) => {
+                if let Err(err) = channel_id
+                    .send_message(&http, CreateMessage::new().add_file(file))
+                    .await
+                {
+                    log::error!("{:?}", err);
+                }
+            }
+            Err(err) => log::error!("{:?}", err),
+        }
+    }
+
+    if let Some(note) = note {
+        send_message(http, channel_id, note).await?;
+    }
+
+    Ok(())
+}
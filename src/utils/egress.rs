@@ -0,0 +1,274 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use reqwest::{
+    Client, Response, Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect,
+};
+
+use crate::{config::agent::EgressConfig, error::VizierError, utils::credential::leaves_host};
+
+/// an address range, e.g. `10.0.0.0/8`
+#[derive(Debug, Clone)]
+struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    fn parse(cidr: &str) -> Option<Self> {
+        let (addr, prefix) = match cidr.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, prefix.parse::<u8>().ok()?),
+            None => {
+                let addr = cidr.parse::<IpAddr>().ok()?;
+                (addr, if addr.is_ipv4() { 32 } else { 128 })
+            }
+        };
+
+        Some(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        // ipv4 mapped ipv6 addresses are matched as ipv4
+        let ip = match ip {
+            IpAddr::V6(v6) => v6
+                .to_ipv4_mapped()
+                .map(IpAddr::V4)
+                .unwrap_or(IpAddr::V6(*v6)),
+            ip => *ip,
+        };
+
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix.min(32) as u32)
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix.min(128) as u32)
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// the egress config of an agent, enforced on every hop of a request
+#[derive(Debug, Clone)]
+pub struct EgressPolicy {
+    allowed_domains: Vec<String>,
+    blocked: Vec<Cidr>,
+    max_response_bytes: usize,
+    max_redirects: usize,
+    timeout: std::time::Duration,
+}
+
+impl EgressPolicy {
+    pub fn new(config: &EgressConfig) -> Self {
+        let blocked = config
+            .blocked_cidrs
+            .iter()
+            .filter_map(|cidr| {
+                let parsed = Cidr::parse(cidr);
+                if parsed.is_none() {
+                    log::warn!("invalid cidr {} in the egress config", cidr);
+                }
+                parsed
+            })
+            .collect();
+
+        Self {
+            allowed_domains: config.allowed_domains.clone(),
+            blocked,
+            max_response_bytes: config.max_response_bytes,
+            max_redirects: config.max_redirects,
+            timeout: *config.timeout,
+        }
+    }
+
+    fn is_blocked(&self, ip: &IpAddr) -> bool {
+        self.blocked.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    fn is_allowed_domain(&self, host: &str) -> bool {
+        self.allowed_domains.is_empty()
+            || self
+                .allowed_domains
+                .iter()
+                .any(|domain| match domain.strip_prefix("*.") {
+                    Some(parent) => host == parent || host.ends_with(&format!(".{}", parent)),
+                    None => host == domain,
+                })
+    }
+
+    /// checks the scheme and host of a url, hostnames are checked again once
+    /// resolved
+    pub fn check(&self, url: &Url) -> Result<(), VizierError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(VizierError(format!(
+                "{} is not allowed, only http and https are",
+                url.scheme()
+            )));
+        }
+
+        let host = url
+            .host_str()
+            .ok_or(VizierError(format!("{} has no host", url)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_lowercase();
+
+        if !self.is_allowed_domain(&host) {
+            return Err(VizierError(format!("{} is not an allowed domain", host)));
+        }
+
+        if host.parse::<IpAddr>().is_ok_and(|ip| self.is_blocked(&ip)) {
+            return Err(VizierError(format!("{} is a blocked address", host)));
+        }
+
+        Ok(())
+    }
+
+    /// a client that checks every redirect and every resolved address
+    pub fn client(&self) -> Result<Client, VizierError> {
+        self.build_client(false)
+    }
+
+    /// with a credential, redirects to other hosts are returned as they are
+    fn build_client(&self, credential: bool) -> Result<Client, VizierError> {
+        let policy = Arc::new(self.clone());
+
+        let redirect_policy = policy.clone();
+        let redirect = redirect::Policy::custom(move |attempt| {
+            if credential && leaves_host(&attempt) {
+                return attempt.stop();
+            }
+            if attempt.previous().len() > redirect_policy.max_redirects {
+                return attempt.error(format!(
+                    "more than {} redirects",
+                    redirect_policy.max_redirects
+                ));
+            }
+
+            match redirect_policy.check(attempt.url()) {
+                Ok(_) => attempt.follow(),
+                Err(err) => attempt.error(err.0),
+            }
+        });
+
+        Client::builder()
+            .timeout(self.timeout)
+            .redirect(redirect)
+            // a proxy would resolve the host itself, past the policy
+            .no_proxy()
+            .dns_resolver(Arc::new(EgressResolver(policy)))
+            .build()
+            .map_err(|err| VizierError(err.to_string()))
+    }
+
+    /// checks the url and builds the client for a request to it, which may
+    /// carry the named credential
+    pub fn client_for(&self, url: &str, credential: Option<&str>) -> Result<Client, VizierError> {
+        let url = Url::parse(url).map_err(|err| VizierError(format!("{}: {}", url, err)))?;
+        self.check(&url)?;
+
+        self.build_client(credential.is_some())
+    }
+
+    /// reads the response body, failing once it outgrows the limit
+    pub async fn read_body(&self, mut response: Response) -> Result<Vec<u8>, VizierError> {
+        let too_large = || {
+            VizierError(format!(
+                "response is larger than {} bytes",
+                self.max_response_bytes
+            ))
+        };
+
+        if response
+            .content_length()
+            .is_some_and(|len| len as usize > self.max_response_bytes)
+        {
+            return Err(too_large());
+        }
+
+        let mut body = vec![];
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|err| VizierError(err.to_string()))?
+        {
+            if body.len() + chunk.len() > self.max_response_bytes {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(body)
+    }
+
+    pub async fn read_text(&self, response: Response) -> Result<String, VizierError> {
+        let body = self.read_body(response).await?;
+
+        Ok(String::from_utf8_lossy(&body).to_string())
+    }
+}
+
+/// drops the blocked addresses of a hostname, so DNS can't point the tools
+/// at a private address
+struct EgressResolver(Arc<EgressPolicy>);
+
+impl Resolve for EgressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.0.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| !policy.is_blocked(&addr.ip()))
+                .collect::<Vec<SocketAddr>>();
+
+            if addrs.is_empty() {
+                return Err(format!("{} resolves to a blocked address", host).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(cidr: &str, ip: &str) -> bool {
+        Cidr::parse(cidr).unwrap().contains(&ip.parse().unwrap())
+    }
+
+    #[test]
+    fn cidr_contains_v4() {
+        assert!(contains("10.0.0.0/8", "10.1.2.3"));
+        assert!(!contains("10.0.0.0/8", "11.0.0.1"));
+        assert!(contains("0.0.0.0/0", "8.8.8.8"));
+        assert!(contains("127.0.0.1", "127.0.0.1"));
+        assert!(!contains("127.0.0.1", "127.0.0.2"));
+    }
+
+    #[test]
+    fn cidr_contains_v6() {
+        assert!(contains("fc00::/7", "fd12::1"));
+        assert!(!contains("fc00::/7", "2001:db8::1"));
+        assert!(!contains("10.0.0.0/8", "::1"));
+    }
+
+    #[test]
+    fn cidr_contains_mapped_v4() {
+        assert!(contains("127.0.0.0/8", "::ffff:127.0.0.1"));
+        assert!(!contains("127.0.0.0/8", "::ffff:8.8.8.8"));
+    }
+}
@@ -1,9 +1,14 @@
 use regex::Regex;
 use std::path::PathBuf;
 
+use crate::schema::VizierAttachment;
+
+pub mod credential;
 pub mod discord;
+pub mod egress;
 pub mod markdown;
 pub mod ollama;
+pub mod openai;
 pub mod tar;
 pub mod telegram;
 
@@ -14,6 +19,22 @@ pub fn remove_think_tags(text: &str) -> String {
     text
 }
 
+/// Keep the last `max_chars` characters of `text`, prefixed with an ellipsis when cut.
+///
+/// Used to preview streamed responses that outgrow a channel's message size limit.
+pub fn truncate_head(text: &str, max_chars: usize) -> String {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return text.to_string();
+    }
+
+    let tail = text
+        .chars()
+        .skip(len - max_chars.saturating_sub(1))
+        .collect::<String>();
+    format!("…{}", tail)
+}
+
 /// Build a filesystem path in a cross-platform way using PathBuf.
 ///
 /// This ensures compatibility on Windows (which uses backslashes) and Unix systems.
@@ -84,8 +105,43 @@ pub fn format_thinking(name: &String, args: &serde_json::Value) -> String {
     format!("{} {}", title, content)
 }
 
+/// body of an approval prompt, the arguments are cut to fit a chat message
+pub fn format_approval_request(name: &String, args: &serde_json::Value) -> String {
+    let mut args = serde_json::to_string_pretty(args).unwrap();
+    if args.chars().count() > 1500 {
+        args = format!("{}…", args.chars().take(1500).collect::<String>());
+    }
+
+    format!("approval needed to use {}\n```js\n{}\n```", name, args)
+}
+
 pub fn get_mime_type(filename: &str) -> String {
     mime_guess::from_path(filename)
         .first_or_text_plain()
         .to_string()
 }
+
+/// splits off the attachments above a channel's size limit, with a note about
+/// them for the user
+pub fn split_oversized_attachments(
+    attachments: Vec<VizierAttachment>,
+    max_size: usize,
+) -> (Vec<VizierAttachment>, Option<String>) {
+    let (fits, oversized): (Vec<_>, Vec<_>) = attachments
+        .into_iter()
+        .partition(|attachment| attachment.size().is_none_or(|size| size <= max_size));
+
+    let note = (!oversized.is_empty()).then(|| {
+        format!(
+            "couldn't send {}, files here are limited to {} MB",
+            oversized
+                .iter()
+                .map(|attachment| format!("`{}`", attachment.filename))
+                .collect::<Vec<_>>()
+                .join(", "),
+            max_size / 1024 / 1024
+        )
+    });
+
+    (fits, note)
+}
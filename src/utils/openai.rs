@@ -0,0 +1,22 @@
+use anyhow::Result;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rig::providers::openai;
+
+use crate::config::provider::CompatibleProviderConfig;
+
+/// client of a `providers.compatible` entry, with its extra headers on every request
+pub fn compatible_client(config: &CompatibleProviderConfig) -> Result<openai::Client> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in config.headers.iter() {
+        headers.insert(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+
+    Ok(openai::Client::builder()
+        .base_url(&config.base_url)
+        .api_key(config.api_key.clone().unwrap_or_default())
+        .http_headers(headers)
+        .build()?)
+}
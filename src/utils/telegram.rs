@@ -1,10 +1,19 @@
 use teloxide::Bot;
 use teloxide::prelude::*;
-use teloxide::types::Recipient;
+use teloxide::types::{
+    InlineKeyboardButton, InlineKeyboardMarkup, InputFile, MessageId, Recipient,
+};
 
-use crate::error::VizierError;
+use crate::{
+    error::{VizierError, throw_vizier_error},
+    schema::{VizierAttachment, VizierAttachmentContent},
+    utils::{get_mime_type, split_oversized_attachments, truncate_head},
+};
 
 const MAX_MESSAGE_LENGTH: usize = 4096;
+// bot api upload limits
+const MAX_ATTACHMENT_SIZE: usize = 50 * 1024 * 1024;
+const MAX_PHOTO_SIZE: usize = 10 * 1024 * 1024;
 
 fn escape_markdown_v2(text: &str) -> String {
     let reserved = [
@@ -59,3 +68,138 @@ where
     Ok(())
 }
 
+/// send or edit the in-progress message of a streamed response as plain text,
+/// partial markdown would be rejected by the MarkdownV2 parser
+pub async fn stream_message<C>(
+    bot: &Bot,
+    recipient: C,
+    message_id: Option<MessageId>,
+    content: &str,
+) -> Result<MessageId, VizierError>
+where
+    C: Into<Recipient>,
+{
+    let preview = truncate_head(content, MAX_MESSAGE_LENGTH - 1);
+
+    let res = match message_id {
+        None => bot.send_message(recipient, preview).await,
+        Some(message_id) => bot.edit_message_text(recipient, message_id, preview).await,
+    };
+
+    match res {
+        Ok(message) => Ok(message.id),
+        Err(err) => throw_vizier_error("streaming message", err),
+    }
+}
+
+/// replace the streamed message with the final markdown content, falls back to
+/// fresh messages when the content no longer fits into a single telegram message
+pub async fn finalize_stream_message<C, T>(
+    bot: &Bot,
+    recipient: C,
+    message_id: MessageId,
+    content: T,
+) -> Result<(), VizierError>
+where
+    C: Into<Recipient>,
+    T: Into<String>,
+{
+    let content = content.into();
+    let escaped_content = escape_markdown_v2(&content);
+    let recipient = recipient.into();
+
+    if escaped_content.len() < MAX_MESSAGE_LENGTH {
+        if let Err(err) = bot
+            .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+            .edit_message_text(recipient.clone(), message_id, escaped_content)
+            .await
+        {
+            log::error!("{:?}", err);
+        }
+        return Ok(());
+    }
+
+    if let Err(err) = bot.delete_message(recipient.clone(), message_id).await {
+        log::error!("{:?}", err);
+    }
+
+    send_message(bot, recipient, content).await
+}
+
+/// approval prompt with approve/deny buttons, answered through the
+/// `approve:<id>` / `deny:<id>` callback queries
+pub async fn send_approval_request<C>(
+    bot: &Bot,
+    recipient: C,
+    approval_id: &str,
+    content: String,
+) -> Result<MessageId, VizierError>
+where
+    C: Into<Recipient>,
+{
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("Approve", format!("approve:{}", approval_id)),
+        InlineKeyboardButton::callback("Deny", format!("deny:{}", approval_id)),
+    ]]);
+
+    match bot
+        .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+        .send_message(recipient.into(), escape_markdown_v2(&content))
+        .reply_markup(keyboard)
+        .await
+    {
+        Ok(message) => Ok(message.id),
+        Err(err) => throw_vizier_error("sending approval request", err),
+    }
+}
+
+/// upload the files of a response, images small enough are sent as photos
+pub async fn send_attachments<C>(
+    bot: &Bot,
+    recipient: C,
+    attachments: Vec<VizierAttachment>,
+) -> Result<(), VizierError>
+where
+    C: Into<Recipient>,
+{
+    let recipient = recipient.into();
+    let (attachments, note) = split_oversized_attachments(attachments, MAX_ATTACHMENT_SIZE);
+
+    for attachment in attachments {
+        let file = match &attachment.content {
+            VizierAttachmentContent::Url(url) => match url.parse() {
+                Ok(url) => InputFile::url(url),
+                Err(err) => {
+                    log::error!("{}: {}", attachment.filename, err);
+                    continue;
+                }
+            },
+            _ => match attachment.bytes() {
+                Ok(bytes) => InputFile::memory(bytes.unwrap_or_default()),
+                Err(err) => {
+                    log::error!("{}: {}", attachment.filename, err);
+                    continue;
+                }
+            },
+        }
+        .file_name(attachment.filename.clone());
+
+        let is_photo = get_mime_type(&attachment.filename).starts_with("image/")
+            && attachment.size().is_some_and(|size| size <= MAX_PHOTO_SIZE);
+        let res = if is_photo {
+            bot.send_photo(recipient.clone(), file).await
+        } else {
+            bot.send_document(recipient.clone(), file).await
+        };
+
+        if let Err(err) = res {
+            log::error!("{:?}", err);
+        }
+    }
+
+    if let Some(note) = note {
+        send_message(bot, recipient, note).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, fs, path::PathBuf, time::Duration};
+
+use chrono::Utc;
+use vizier::{
+    agents::process::agent_process,
+    config::{
+        ChannelsConfig, VizierConfig,
+        agent::AgentConfig,
+        shell::{LocalShellConfig, ShellConfig},
+    },
+    dependencies::VizierDependencies,
+    schema::{
+        VizierChannelId, VizierRequest, VizierRequestContent, VizierResponse,
+        VizierResponseContent, VizierSession,
+    },
+};
+
+const SCRIPT: &str = r#"
+completions:
+  - matches: "60 character title"
+    repeat: true
+    text: "Weather check"
+  - tool_calls:
+      - name: think
+        arguments: { thought: "checking the weather" }
+    usage: { input_tokens: 120, output_tokens: 12 }
+  - text: "it's sunny"
+    usage: { input_tokens: 150, output_tokens: 4 }
+"#;
+
+const AGENT: &str = r#"
+name: Tester
+description: answers from a script
+provider: mock
+model: MODEL
+session_memory:
+  max_capacity: 10
+thinking_depth: 5
+tools:
+  timeout: 30s
+silent_read_initiative_chance: 0
+show_thinking: false
+show_tool_calls: true
+stream_response: false
+prompt_timeout: 1m
+heartbeat_interval: 1h
+dream_interval: 1h
+"#;
+
+fn workspace() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vizier-test-{}", nanoid::nanoid!(8)));
+    fs::create_dir_all(&path).unwrap();
+
+    path
+}
+
+async fn deps(workspace: &PathBuf) -> VizierDependencies {
+    let script = workspace.join("script.yaml");
+    fs::write(&script, SCRIPT).unwrap();
+
+    let agent = AGENT.replace("MODEL", &script.to_string_lossy());
+    let mut agent = serde_yaml::from_str::<AgentConfig>(&agent).unwrap();
+    agent.system_prompt = Some("you are a test agent".into());
+
+    let config = VizierConfig {
+        workspace: workspace.join(".vizier").to_string_lossy().to_string(),
+        embedding: None,
+        agents: HashMap::from([("tester".to_string(), agent)]),
+        channels: ChannelsConfig {
+            discord: None,
+            http: None,
+            telegram: None,
+        },
+        shell: ShellConfig::Local(LocalShellConfig {
+            path: workspace.to_string_lossy().to_string(),
+            env: None,
+        }),
+        ..VizierConfig::default()
+    };
+
+    VizierDependencies::new(config).await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn chat_runs_tools_and_answers() {
+    let workspace = workspace();
+    let deps = deps(&workspace).await;
+    let mut responses = deps.transport.subscribe_response().await.unwrap();
+
+    let process = tokio::spawn(agent_process("tester".into(), deps.clone()));
+
+    let session = VizierSession("tester".into(), VizierChannelId::HTTP("test".into()), None);
+    deps.transport
+        .send_request(
+            session.clone(),
+            VizierRequest {
+                timestamp: Utc::now(),
+                user: deps.config.primary_user.username.clone(),
+                content: VizierRequestContent::Chat("what's the weather?".into()),
+                metadata: serde_json::json!({}),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let mut tool_calls = vec![];
+    let (content, stats) = tokio::time::timeout(Duration::from_secs(30), async {
+        loop {
+            let (res_session, VizierResponse { content, .. }) = responses.recv().await.unwrap();
+            if res_session != session {
+                continue;
+            }
+
+            match content {
+                VizierResponseContent::ToolChoice { name, .. } => tool_calls.push(name),
+                VizierResponseContent::Message { content, stats } => break (content, stats),
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("the agent didn't answer");
+
+    assert_eq!(content, "it's sunny");
+    assert_eq!(tool_calls, vec!["think".to_string()]);
+
+    let stats = stats.expect("the answer has no stats");
+    assert_eq!(stats.total_input_tokens, 270);
+    assert_eq!(stats.total_output_tokens, 16);
+
+    process.abort();
+    let _ = fs::remove_dir_all(&workspace);
+}
@@ -5,7 +5,7 @@ import rehypeHighlight from 'rehype-highlight'
 
 interface InlineEvent {
   id: string
-  type: 'start' | 'tool_choice' | 'thinking'
+  type: 'start' | 'tool_choice' | 'thinking' | 'message_delta'
   content?: string
   timestamp: number
 }
@@ -68,6 +68,13 @@ function ThinkingIndicatorComponent({ inlineEvents, agentName }: ThinkingIndicat
                 </ReactMarkdown>
               </div>
             )}
+            {evt.type === 'message_delta' && evt.content && (
+              <div className="prose">
+                <ReactMarkdown remarkPlugins={[remarkGfm]} rehypePlugins={[rehypeHighlight]}>
+                  {evt.content}
+                </ReactMarkdown>
+              </div>
+            )}
             {evt.type === 'thinking' && evt.content && (
               <div className="prose">
                 <ReactMarkdown remarkPlugins={[remarkGfm]} rehypePlugins={[rehypeHighlight]}>
@@ -56,6 +56,9 @@ export type VizierResponseContent =
   | 'thinking_start'
   | { thinking: string }
   | { tool_choice: { name: string; args: Record<string, unknown> } }
+  | { approval_request: { id: string; name: string; args: Record<string, unknown> } }
+  | { approval_resolved: { id: string; approved: boolean; by: string } }
+  | { message_delta: string }
   | { message: { content: string; stats?: VizierResponseStats } }
   | 'empty'
   | 'abort'
@@ -84,6 +87,9 @@ export interface VizierResponseStats {
   total_output_tokens: number
   total_tokens: number
   duration: { secs: number; nanos: number }
+  model?: string
+  cost?: number
+  reasoning?: string
 }
 
 export interface ChatMessage {
@@ -108,6 +114,7 @@ export interface WebSocketMessage {
   content: VizierRequestContent
   metadata?: Record<string, unknown>
   attachments?: VizierAttachment[]
+  response_schema?: Record<string, unknown>
 }
 
 // WebSocketResponse matches backend VizierResponse struct
@@ -179,15 +186,18 @@ export interface UpdateTaskRequest extends CreateTaskRequest { }
 export interface UsageSummary {
   total_tokens: number
   total_input_tokens: number
+  total_cached_input_tokens: number
   total_output_tokens: number
   total_requests: number
   avg_duration_ms: number
+  total_cost: number
 }
 
 export interface ChannelUsage {
   channel_id: string
   total_tokens: number
   total_requests: number
+  total_cost: number
 }
 
 export interface ChannelTypeUsageDetail {
@@ -195,6 +205,7 @@ export interface ChannelTypeUsageDetail {
   input_tokens: number
   output_tokens: number
   total_requests: number
+  cost: number
 }
 
 export interface DailyChannelTypeUsage {
@@ -205,6 +216,7 @@ export interface DailyChannelTypeUsage {
 export interface ChannelTypeUsage {
   total_tokens: number
   total_requests: number
+  total_cost: number
   channels: ChannelUsage[]
 }
 
@@ -214,6 +226,7 @@ export interface DailyUsage {
   input_tokens: number
   output_tokens: number
   total_requests: number
+  cost: number
 }
 
 export interface AgentUsageStats {
@@ -4,7 +4,7 @@ import { useParams, useNavigate } from 'react-router'
 import useWebSocket, { ReadyState } from 'react-use-websocket'
 import { getTopicHistory, getChatWebSocketUrl, listTopics, getAgentDetail, listAgents, uploadFile, base_url } from '../services/vizier'
 import { autoCorrectSlug, autoCorrectSlugStrict } from '../utils/slug'
-import type { Agent, ChatMessage, Topic, VizierAttachment, WebSocketMessage, WebSocketResponse, VizierResponseStats } from '../interfaces/types'
+import type { Agent, ChatMessage, Topic, VizierAttachment, WebSocketMessage, WebSocketResponse, VizierResponseStats, VizierRequestContent } from '../interfaces/types'
 import ReactMarkdown from 'react-markdown'
 import remarkGfm from 'remark-gfm'
 import rehypeHighlight from 'rehype-highlight'
@@ -30,7 +30,7 @@ const textareaStyle = `
 
 interface InlineEvent {
   id: string
-  type: 'start' | 'tool_choice' | 'thinking'
+  type: 'start' | 'tool_choice' | 'thinking' | 'message_delta'
   content?: string
   timestamp: number
 }
@@ -139,6 +139,7 @@ export default function Chat() {
   const [agentDetail, setAgentDetail] = useState<Agent | null>(null)
   const [attachments, setAttachments] = useState<VizierAttachment[]>([])
   const [uploading, setUploading] = useState(false)
+  const [pendingApprovals, setPendingApprovals] = useState<{ id: string; content: string }[]>([])
   const messagesEndRef = useRef<HTMLDivElement>(null)
   const inputRef = useRef<HTMLTextAreaElement>(null)
   const fileInputRef = useRef<HTMLInputElement>(null)
@@ -168,6 +169,18 @@ export default function Chat() {
       onOpen: () => console.log('WebSocket connected'),
       onClose: () => console.log('WebSocket disconnected'),
       onError: (e) => console.error('WebSocket error:', e),
+      // deltas arrive faster than lastJsonMessage re-renders, so they are folded in here
+      onMessage: (event) => {
+        try {
+          const wsResponse = JSON.parse(event.data) as WebSocketResponse
+          const content = wsResponse?.content
+          if (typeof content === 'object' && content !== null && 'message_delta' in content) {
+            appendMessageDelta(content.message_delta)
+          }
+        } catch {
+          // non-json frames are handled elsewhere
+        }
+      },
     }
   )
 
@@ -266,6 +279,21 @@ export default function Chat() {
     }])
   }
 
+  const appendMessageDelta = (delta: string) => {
+    setInlineEvents(prev => {
+      const last = prev[prev.length - 1]
+      if (last && last.type === 'message_delta') {
+        return [...prev.slice(0, -1), { ...last, content: (last.content ?? '') + delta }]
+      }
+      return [...prev, {
+        id: Date.now().toString() + Math.random().toString(36).substr(2, 9),
+        type: 'message_delta',
+        content: delta,
+        timestamp: Date.now(),
+      }]
+    })
+  }
+
   // Handle incoming WebSocket messages
   useEffect(() => {
     console.log('WebSocket message received:', lastJsonMessage)
@@ -291,6 +319,7 @@ export default function Chat() {
 
       case 'abort':
         clearInlineEvents()
+        setPendingApprovals([])
         return
     }
 
@@ -306,6 +335,18 @@ export default function Chat() {
         return
       }
 
+      if ('approval_request' in content) {
+        const { id, name, args } = content.approval_request
+        setPendingApprovals(prev => [...prev, { id, content: formatToolChoice(name, args, agentNames) }])
+        return
+      }
+
+      if ('approval_resolved' in content) {
+        const { id } = content.approval_resolved
+        setPendingApprovals(prev => prev.filter(approval => approval.id !== id))
+        return
+      }
+
       if ('message' in content) {
         clearInlineEvents()
         setMessages(prev => {
@@ -374,11 +415,14 @@ export default function Chat() {
     }
 
     const username = getCurrentUsername()
+    const text = currentInput.trim()
+    // slash commands are handled by the agent's command registry
+    const content: VizierRequestContent = text.startsWith('/') ? { command: text } : { chat: text }
 
     const message: WebSocketMessage = {
       timestamp: new Date().toISOString(),
       user: username,
-      content: { chat: currentInput.trim() },
+      content,
       metadata: null as any,
       attachments: attachments.length > 0 ? attachments : undefined,
     }
@@ -396,7 +440,7 @@ export default function Chat() {
         Request: {
           timestamp: new Date().toISOString(),
           user: username,
-          content: { chat: currentInput.trim() },
+          content,
           attachments: attachments.length > 0 ? attachments : undefined,
         },
       },
@@ -414,6 +458,19 @@ export default function Chat() {
     console.log('sendJsonMessage called, readyState now:', readyState)
   }, [agentId, topicId, readyState, sendJsonMessage, attachments])
 
+  // answered like the /approve and /deny commands
+  const handleApproval = useCallback((id: string, approve: boolean) => {
+    const message: WebSocketMessage = {
+      timestamp: new Date().toISOString(),
+      user: getCurrentUsername(),
+      content: { command: `/${approve ? 'approve' : 'deny'} ${id}` },
+      metadata: null as any,
+    }
+
+    sendJsonMessage(message)
+    setPendingApprovals(prev => prev.filter(approval => approval.id !== id))
+  }, [sendJsonMessage])
+
   const handleKeyDown = useCallback((e: React.KeyboardEvent) => {
     if (e.key === 'Enter' && !e.shiftKey) {
       e.preventDefault()
@@ -606,6 +663,8 @@ export default function Chat() {
                 const request = msg.content.Request as any
                 if (request.content?.chat) {
                   content = request.content.chat
+                } else if (request.content?.command) {
+                  content = request.content.command
                 }
                 senderName = request.user || 'You'
                 msgAttachments = request.attachments
@@ -616,6 +675,7 @@ export default function Chat() {
                 }
                 senderName = agentDetail?.name || 'Agent'
                 stats = response?.content?.message?.stats as VizierResponseStats | undefined
+                msgAttachments = response?.attachments?.length ? response.attachments : undefined
               }
 
               if (!content) return null
@@ -658,6 +718,33 @@ export default function Chat() {
           maxWidth: '900px',
           margin: '0 auto',
         }}>
+          {/* Tool calls waiting for approval */}
+          {pendingApprovals.map(approval => (
+            <div
+              key={approval.id}
+              style={{
+                display: 'flex',
+                flexDirection: 'column',
+                gap: '8px',
+                padding: '12px',
+                background: 'var(--surface)',
+                border: '1px solid var(--border)',
+                borderRadius: '8px',
+                fontSize: '13px',
+              }}
+            >
+              <span style={{ color: 'var(--text-tertiary)' }}>Approval needed</span>
+              <ReactMarkdown remarkPlugins={[remarkGfm]}>{approval.content}</ReactMarkdown>
+              <div style={{ display: 'flex', gap: '8px' }}>
+                <button className="btn btn-primary" onClick={() => handleApproval(approval.id, true)}>
+                  Approve
+                </button>
+                <button className="btn btn-secondary" onClick={() => handleApproval(approval.id, false)}>
+                  Deny
+                </button>
+              </div>
+            </div>
+          ))}
           {/* Attachment chips */}
           {attachments.length > 0 && (
             <div style={{
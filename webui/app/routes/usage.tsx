@@ -324,7 +324,7 @@ export default function UsageDashboard() {
           <div style={{ display: 'flex', gap: '12px', alignItems: 'center' }}></div>
         </div>
 
-        <div style={{ display: 'grid', gridTemplateColumns: 'repeat(4, 1fr)', gap: '16px', marginBottom: '32px' }}>
+        <div style={{ display: 'grid', gridTemplateColumns: 'repeat(5, 1fr)', gap: '16px', marginBottom: '32px' }}>
           <div style={{ background: 'var(--surface)', borderRadius: '8px', padding: '16px' }}>
             <div style={{ fontSize: '12px', color: 'var(--text-secondary)', marginBottom: '4px' }}>
               Total Tokens
@@ -357,6 +357,14 @@ export default function UsageDashboard() {
               {formatNumber(usage.summary.total_requests)}
             </div>
           </div>
+          <div style={{ background: 'var(--surface)', borderRadius: '8px', padding: '16px' }}>
+            <div style={{ fontSize: '12px', color: 'var(--text-secondary)', marginBottom: '4px' }}>
+              Total Cost
+            </div>
+            <div style={{ fontSize: '24px', fontWeight: 600 }}>
+              ${(usage.summary.total_cost ?? 0).toFixed(2)}
+            </div>
+          </div>
         </div>
 
         <div style={{ display: 'grid', gridTemplateColumns: 'repeat(2, 1fr)', gap: '16px', marginBottom: '24px' }}>